@@ -1,6 +1,5 @@
-use std::{fs, path::PathBuf};
-use serde_json::from_str;
-use crate::{config::{self, Cli}, css::generate_palette_css};
+use std::{fs, path::{Path, PathBuf}};
+use crate::{config, css::{generate_palette_css, render_palette_block}};
 
 pub enum AppState {
   Uninitialized,
@@ -10,17 +9,20 @@ pub enum AppState {
 }
 
 pub trait StateBehavior {
-  fn load_config(self, cli: &Cli) -> Result<AppState, Box<dyn std::error::Error>>;
+  fn load_config(self, cwd: &Path) -> Result<AppState, Box<dyn std::error::Error>>;
   fn validate(self) -> Result<AppState, Box<dyn std::error::Error>>;
-  fn generate_css(self) -> Result<AppState, Box<dyn std::error::Error>>;
+  fn generate_css(self, out_dir: &Path) -> Result<AppState, Box<dyn std::error::Error>>;
 }
 
 impl StateBehavior for AppState {
-  fn load_config(self, cli: &Cli) -> Result<AppState, Box<dyn std::error::Error>> {
+  /// Loads the user-level config layered with every `color-gen.toml` found
+  /// walking up from `cwd`, then applies `COLOR_GEN__...` env overrides -
+  /// the same `layered_config` resolution `tokens transform` uses, so
+  /// project-level config and env overrides apply to the primary CLI path too.
+  fn load_config(self, cwd: &Path) -> Result<AppState, Box<dyn std::error::Error>> {
     match self {
       AppState::Uninitialized => {
-        let data = fs::read_to_string(&cli.config)?;
-        let config: config::Config = from_str(&data)?;
+        let config = config::layered_config(cwd)?;
 
         Ok(AppState::ConfigLoaded(config))
       }
@@ -41,10 +43,33 @@ impl StateBehavior for AppState {
     }
   }
 
-  fn generate_css(self) -> Result<AppState, Box<dyn std::error::Error>> {
+  /// Writes each theme's CSS file under `out_dir` (created if missing)
+  /// rather than always relative to the process's current directory, so
+  /// `--out`/`--cwd` actually reach the one place files get written.
+  fn generate_css(self, out_dir: &Path) -> Result<AppState, Box<dyn std::error::Error>> {
     match self {
       AppState::Validated(config) => {
         let mut css_files = Vec::new();
+        fs::create_dir_all(out_dir)?;
+
+        for theme in &config.themes {
+          let mut css = String::new();
+
+          for (variant_name, base) in &theme.variants {
+            let palette_config = config::PaletteConfig {
+              base: base.clone(),
+              variant: Some(variant_name.clone()),
+            };
+            let palette = generate_palette_css(&theme.name, &palette_config)?;
+            let selector = format!(":root[data-theme=\"{}\"]", variant_name);
+            css.push_str(&render_palette_block(&palette, &selector));
+            css.push('\n');
+          }
+
+          let path = out_dir.join(format!("{}.css", theme.name));
+          fs::write(&path, css)?;
+          css_files.push(path);
+        }
 
         Ok(AppState::Generated(config, css_files))
       }
@@ -1,6 +1,8 @@
 use std::path::{Path, PathBuf};
 use std::fs;
+use indexmap::IndexMap;
 use serde::{Serialize, Deserialize};
+use thiserror::Error;
 
 pub mod cli {
   use std::path::PathBuf;
@@ -23,7 +25,12 @@ pub mod cli {
   #[derive(Debug, Subcommand)]
   enum SubCommands {
     Path,
-    Edit
+    Edit,
+    /// Print the JSON Schema for the config file, for editor autocompletion/validation
+    Schema {
+      #[arg(long, value_name = "PATH")]
+      out: Option<PathBuf>,
+    },
   }
 
   pub fn handle(cmd: &Commands) {
@@ -35,7 +42,7 @@ pub mod cli {
             Err(e) => eprintln!("{BG_RED}Failed to load config from {:?}: {RESET}{e}", path),
           }
         }
-        
+
         match subcommands {
           SubCommands::Path => {
             println!("{DIM_GREEN}Config path{RESET}{BOLD} => {:?}{RESET}", APP_DIRS.config_dir);
@@ -45,6 +52,19 @@ pub mod cli {
             if let Err(e) = that(config_path) {
               eprintln!("{BG_RED}Failed to open config file: {RESET}{e}");
             }
+          },
+          SubCommands::Schema { out } => {
+            let schema = schemars::schema_for!(crate::config::Config);
+            let pretty = serde_json::to_string_pretty(&schema)
+              .expect("Failed to serialize generated JSON Schema");
+
+            match out {
+              Some(path) => match std::fs::write(path, pretty) {
+                Ok(_) => println!("{DIM_GREEN}Schema written to {:?}{RESET}", path),
+                Err(e) => eprintln!("{BG_RED}Failed to write schema to {:?}: {RESET}{e}", path),
+              },
+              None => println!("{pretty}"),
+            }
           }
         }
       }
@@ -52,26 +72,124 @@ pub mod cli {
   }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Error)]
+pub enum ConfigError {
+  #[error("failed to read config file {path:?}: {source}")]
+  Io { path: PathBuf, source: std::io::Error },
+  #[error("failed to parse config as TOML: {0}")]
+  Toml(#[from] toml::de::Error),
+  #[error("failed to serialize config as TOML: {0}")]
+  TomlSer(#[from] toml::ser::Error),
+  #[error("unsupported config file extension '.{0}'; expected .json, .yaml/.yml, or .toml")]
+  UnsupportedFormat(String),
+  #[error("config file has no extension; expected .json, .yaml/.yml, or .toml")]
+  MissingFormat,
+  #[error("{0}")]
+  Invalid(String),
+}
+
+/// Mirrors starship's `ModuleConfig`: a type that can be carved out of a raw
+/// `toml::Value`, substituting its `Default` (with a warning) for any field
+/// that fails to parse instead of aborting the whole load.
+pub trait LoadConfig: Sized + Default {
+  fn from_value(value: &toml::Value) -> Result<Self, ConfigError>;
+
+  fn load(value: &toml::Value) -> Self {
+    match Self::from_value(value) {
+      Ok(parsed) => parsed,
+      Err(e) => {
+        eprintln!("{}Warning: {e}, falling back to default{}", simply_colored::DIM_YELLOW, simply_colored::RESET);
+        Self::default()
+      }
+    }
+  }
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Config { transforms: Vec::new(), themes: Vec::new() }
+  }
+}
+
+impl LoadConfig for Config {
+  fn from_value(value: &toml::Value) -> Result<Self, ConfigError> {
+    let table = value.as_table().ok_or_else(|| ConfigError::Invalid("config must be a TOML table".into()))?;
+
+    let transforms = match table.get("transforms") {
+      Some(v) => match v.clone().try_into::<Vec<Transform>>() {
+        Ok(parsed) => parsed,
+        Err(e) => {
+          eprintln!("{}Warning: failed to parse 'transforms' ({e}), using default{}", simply_colored::DIM_YELLOW, simply_colored::RESET);
+          Vec::new()
+        }
+      },
+      None => Vec::new(),
+    };
+
+    let themes = match table.get("themes") {
+      Some(v) => match v.clone().try_into::<Vec<Theme>>() {
+        Ok(parsed) => parsed,
+        Err(e) => {
+          eprintln!("{}Warning: failed to parse 'themes' ({e}), using default{}", simply_colored::DIM_YELLOW, simply_colored::RESET);
+          Vec::new()
+        }
+      },
+      None => Vec::new(),
+    };
+
+    Ok(Config { transforms, themes })
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct Config {
-  pub transforms: Vec<Transform>
+  #[serde(default)]
+  pub transforms: Vec<Transform>,
+  #[serde(default)]
+  pub themes: Vec<Theme>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// One named theme, generating a `:root[data-theme="..."]` CSS block per
+/// variant (e.g. `light`/`dark`) from that variant's base color.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Theme {
+  pub name: String,
+  /// variant name (e.g. "light", "dark") -> base color for that variant
+  pub variants: IndexMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct Transform {
   pub from: PathBuf,
   pub to: Vec<TransformTarget>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct TransformTarget {
   pub format: TargetFormat,
   pub output: PathBuf,
+  /// Namespace prefix prepended to every CSS custom-property name emitted
+  /// for this target (e.g. `"dark"` -> `--dark-color-primary`). Only
+  /// consulted when `format` is `Css`.
+  #[serde(default)]
+  pub css_prefix: Option<String>,
+  /// Separator between normalized path segments in emitted CSS custom
+  /// property names. Defaults to `-`, matching `design_token::CssKeyOptions`.
+  #[serde(default)]
+  pub css_separator: Option<char>,
+  /// Lowercase emitted CSS custom property names. Defaults to `true`.
+  #[serde(default)]
+  pub css_lowercase: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub enum TargetFormat {
+  /// Nested DTCG-shaped JSON ($value/$type/$description), round-tripping
+  /// through `design_token::TokenJson`.
   Json,
+  /// A flat `{ "dotted.path": "resolved value" }` map - simpler to consume
+  /// than `Json` when a caller just wants final values, not structure.
+  JsonFlat,
   Toml,
   Yaml,
   Scss,
@@ -79,10 +197,182 @@ pub enum TargetFormat {
   Mjs
 }
 
-pub fn load_config(path: &PathBuf) -> Option<Config> {
-  let config_str = std::fs::read_to_string(path).ok()?;
-  let config: Config = toml::from_str(&config_str).ok()?;
-  Some(config)
+/// The base color (and optional theme variant) a named palette is generated from.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PaletteConfig {
+  pub base: String,
+  #[serde(default)]
+  pub variant: Option<String>,
+}
+
+/// Ensure the on-disk config file exists, creating an empty one if needed.
+pub fn ensure_config() -> Result<(), ConfigError> {
+  let config_dir = crate::APP_DIRS.config_dir.clone();
+  if !config_dir.exists() {
+    fs::create_dir_all(&config_dir).map_err(|e| ConfigError::Io { path: config_dir.clone(), source: e })?;
+  }
+
+  let config_file = config_dir.join("config.toml");
+  if !config_file.exists() {
+    fs::write(&config_file, b"# Color Gen Configuration File\n")
+      .map_err(|e| ConfigError::Io { path: config_file, source: e })?;
+  }
+
+  Ok(())
+}
+
+/// Read the internal `config.toml`, filling in defaults (with a warning) for
+/// any section that fails to parse rather than aborting.
+pub fn get_config() -> Result<Config, ConfigError> {
+  ensure_config()?;
+  let config_path = crate::APP_DIRS.config_dir.join("config.toml");
+  let config_str = fs::read_to_string(&config_path).map_err(|e| ConfigError::Io { path: config_path, source: e })?;
+  let value: toml::Value = toml::from_str(&config_str)?;
+  Ok(Config::load(&value))
+}
+
+pub fn save_config(config: &Config) -> Result<(), ConfigError> {
+  let config_path = crate::APP_DIRS.config_dir.join("config.toml");
+  let toml_str = toml::to_string_pretty(config)?;
+  fs::write(&config_path, toml_str).map_err(|e| ConfigError::Io { path: config_path, source: e })
+}
+
+/// Load a `Config` from an arbitrary path, filling in defaults for any
+/// section that fails to parse instead of panicking on the whole file.
+pub fn load_config(path: &PathBuf) -> Result<Config, ConfigError> {
+  let config_str = fs::read_to_string(path).map_err(|e| ConfigError::Io { path: path.clone(), source: e })?;
+  let value: toml::Value = toml::from_str(&config_str)?;
+  Ok(Config::load(&value))
+}
+
+impl Config {
+  /// Concatenate/override `transforms` by their `from` path: a later source
+  /// replaces a transform with the same `from`, leaving earlier entries in
+  /// place otherwise. Mirrors `merge_token_sets`'s override semantics.
+  /// `themes` are layered the same way, keyed by their `name`.
+  pub fn merged(sources: Vec<Config>) -> Config {
+    let mut out = Config::default();
+    for cfg in sources {
+      for t in cfg.transforms {
+        match out.transforms.iter_mut().find(|existing| existing.from == t.from) {
+          Some(existing) => *existing = t,
+          None => out.transforms.push(t),
+        }
+      }
+      for theme in cfg.themes {
+        match out.themes.iter_mut().find(|existing| existing.name == theme.name) {
+          Some(existing) => *existing = theme,
+          None => out.themes.push(theme),
+        }
+      }
+    }
+    out
+  }
+}
+
+const PROJECT_CONFIG_FILENAME: &str = "color-gen.toml";
+const ENV_PREFIX: &str = "COLOR_GEN__";
+
+/// Walk upward from `start`, collecting every `color-gen.toml` found along
+/// the way, ordered furthest-ancestor-first so that the file closest to
+/// `start` sorts last (and therefore wins once layered with `Config::merged`).
+pub fn discover_project_configs(start: &Path) -> Vec<Config> {
+  let mut found = Vec::new();
+  let mut dir = Some(start.to_path_buf());
+
+  while let Some(d) = dir {
+    let candidate = d.join(PROJECT_CONFIG_FILENAME);
+    if candidate.exists() {
+      match load_config(&candidate) {
+        Ok(cfg) => found.push(cfg),
+        Err(e) => eprintln!("{}Warning: failed to load {:?} ({e}), skipping{}", simply_colored::DIM_YELLOW, candidate, simply_colored::RESET),
+      }
+    }
+    dir = d.parent().map(PathBuf::from);
+  }
+
+  found.reverse();
+  found
+}
+
+/// Layer the user-level `config.toml` beneath any `color-gen.toml` files
+/// found walking up from `cwd`, then apply `COLOR_GEN__...`-style
+/// environment variable overrides on top of the merged result.
+pub fn layered_config(cwd: &Path) -> Result<Config, ConfigError> {
+  let mut sources = vec![get_config()?];
+  sources.extend(discover_project_configs(cwd));
+
+  let mut config = Config::merged(sources);
+  apply_env_overrides(&mut config)?;
+  Ok(config)
+}
+
+fn apply_env_overrides(config: &mut Config) -> Result<(), ConfigError> {
+  let mut value = toml::Value::try_from(&*config)?;
+
+  for (key, raw) in std::env::vars() {
+    if let Some(rest) = key.strip_prefix(ENV_PREFIX) {
+      let path: Vec<String> = rest.split("__").map(|s| s.to_ascii_lowercase()).collect();
+      set_toml_path(&mut value, &path, &raw);
+    }
+  }
+
+  *config = value.try_into()?;
+  Ok(())
+}
+
+/// Set a dotted/`__`-joined path inside a `toml::Value`, growing arrays and
+/// tables as needed. Silently ignores paths that don't fit the existing
+/// shape (e.g. an index into a non-array) rather than failing the whole load.
+fn set_toml_path(value: &mut toml::Value, path: &[String], raw: &str) {
+  if path.is_empty() {
+    return;
+  }
+
+  let leaf = parse_env_scalar(raw);
+  let mut cur = value;
+
+  for (i, segment) in path.iter().enumerate() {
+    let is_last = i == path.len() - 1;
+
+    if let Ok(idx) = segment.parse::<usize>() {
+      let arr = match cur.as_array_mut() {
+        Some(a) => a,
+        None => return,
+      };
+      while arr.len() <= idx {
+        arr.push(toml::Value::Table(Default::default()));
+      }
+      if is_last {
+        arr[idx] = leaf;
+        return;
+      }
+      cur = &mut arr[idx];
+    } else {
+      let table = match cur.as_table_mut() {
+        Some(t) => t,
+        None => return,
+      };
+      if is_last {
+        table.insert(segment.clone(), leaf);
+        return;
+      }
+      cur = table.entry(segment.clone()).or_insert_with(|| toml::Value::Table(Default::default()));
+    }
+  }
+}
+
+fn parse_env_scalar(raw: &str) -> toml::Value {
+  if let Ok(b) = raw.parse::<bool>() {
+    return toml::Value::Boolean(b);
+  }
+  if let Ok(i) = raw.parse::<i64>() {
+    return toml::Value::Integer(i);
+  }
+  if let Ok(f) = raw.parse::<f64>() {
+    return toml::Value::Float(f);
+  }
+  toml::Value::String(raw.to_string())
 }
 
 pub fn config_from<P: AsRef<std::path::Path>>(path: P) -> Result<(), String> {
@@ -149,4 +439,4 @@ pub fn config_from<P: AsRef<std::path::Path>>(path: P) -> Result<(), String> {
     .map_err(|e| format!("Failed to write config to {:?}: {}", dest, e))?;
 
   Ok(())
-}
\ No newline at end of file
+}
@@ -4,7 +4,7 @@ use bigcolor::BigColor;
 
 use crate::{config::PaletteConfig, TEMPLATES};
 
-fn tonal_steps() -> [u8; 11] {
+pub fn tonal_steps() -> [u8; 11] {
   [05, 10, 20, 30, 40, 50, 60, 70, 80, 90, 95]
 }
 
@@ -136,12 +136,25 @@ pub struct Palette {
   pub base: CSSBaseToken
 }
 
+impl Palette {
+  /// The tonal step closest to the palette's source color.
+  pub fn key_tone(&self) -> u8 {
+    self.key.value
+  }
+
+  /// The resolved OKLCH string for a given tonal step, if that step exists.
+  pub fn oklch_for_tone(&self, tone: u8) -> Option<String> {
+    self.tokens.get(&tone).map(|t| t.value.to_oklch_string())
+  }
+}
+
 pub fn generate_palette_css(name: &str, config: &PaletteConfig) -> Result<Palette, Box<dyn std::error::Error>> {
   let source_color = BigColor::new(&config.base);
   let source_scale = source_color.monochromatic(Some(tonal_steps().len()));
   let mut color_tokens: HashMap<u8, CSSColorToken> = HashMap::new();
   let key_color = closest_to_base(&source_color, &source_scale)?;
-  let key_tone = source_scale.iter().position(|c| c == &key_color).unwrap() as u8;
+  let key_index = source_scale.iter().position(|c| c == &key_color).unwrap();
+  let key_tone = tonal_steps()[key_index];
 
   for (index, color) in source_scale.iter().enumerate() {
     let tone = tonal_steps()[index];
@@ -158,7 +171,7 @@ pub fn generate_palette_css(name: &str, config: &PaletteConfig) -> Result<Palett
   let key_token = CSSKeyToken::new(
     None,
     name.to_string(),
-    key_tone as u8
+    key_tone
   );
 
   let base_token = CSSBaseToken::new(
@@ -176,6 +189,31 @@ pub fn generate_palette_css(name: &str, config: &PaletteConfig) -> Result<Palett
   })
 }
 
+/// Render one scoped CSS block for a generated palette, e.g.
+/// `:root[data-theme="dark"] { --brand-05: oklch(...); ... }`.
+pub fn render_palette_block(palette: &Palette, selector: &str) -> String {
+  let mut out = String::new();
+  out.push_str(selector);
+  out.push_str(" {\n");
+
+  for tone in tonal_steps() {
+    if let Some(token) = palette.tokens.get(&tone) {
+      out.push_str("  ");
+      out.push_str(&token.to_string(true));
+      out.push('\n');
+    }
+  }
+
+  out.push_str("  ");
+  out.push_str(&palette.base.to_string());
+  out.push('\n');
+  out.push_str("  ");
+  out.push_str(&palette.key.to_string());
+  out.push('\n');
+  out.push_str("}\n");
+  out
+}
+
 fn closest_to_base(base: &BigColor, palette: &Vec<BigColor>) -> anyhow::Result<BigColor> {
   let base_oklch = base.to_oklch();
   let closest = palette
@@ -189,4 +227,20 @@ fn closest_to_base(base: &BigColor, palette: &Vec<BigColor>) -> anyhow::Result<B
     .unwrap_or(palette.get(palette.len() / 2).unwrap());
 
   Ok(closest.clone())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn generate_palette_css_does_not_panic_on_non_default_base() {
+    let config = PaletteConfig { base: "#3366cc".to_string(), variant: None };
+    let palette = generate_palette_css("brand", &config).expect("palette generation should succeed");
+
+    assert!(tonal_steps().contains(&palette.key_tone()));
+    for tone in tonal_steps() {
+      assert!(palette.tokens.contains_key(&tone), "missing token for tone {}", tone);
+    }
+  }
 }
\ No newline at end of file
@@ -3,12 +3,19 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 #[cfg(feature = "js")]
 use boa_engine::{Context as JsContext, Source};
+#[cfg(feature = "rhai")]
+use rhai::{Engine as RhaiEngine, Scope as RhaiScope};
 
 /// Represents the value of a design token, which can be a string, number,
-/// bool, object, alias, reference, color, dimension, transform, or null.
+/// bool, object, alias, reference, color, dimension, transform, palette
+/// reference, or null.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum TokenValue {
+  /// Reference to a tonal step in a named `[palettes]` entry, e.g.
+  /// `{ "palette": "brand", "tone": 20 }` (tone defaults to the palette's
+  /// computed key tone when omitted).
+  PaletteRef { palette: String, #[serde(default)] tone: Option<u8> },
   String(String),
   Number(f64),
   Bool(bool),
@@ -59,31 +66,61 @@ pub enum ResolveError {
   InvalidTransform(String),
   #[error("failed to apply transform: '{0}'")]
   TransformFailed(String),
+  #[error("palette not found: '{0}'")]
+  PaletteNotFound(String),
+  #[error("tonal step {0} not found in palette '{1}'")]
+  ToneNotFound(u8, String),
 }
 
+pub type Palettes = IndexMap<String, crate::config::PaletteConfig>;
+
 // A pluggable registry for transformation functions.
 type TransformFn = fn(&TransformRegistry, &mut TransformContext, Option<TokenValue>, &TransformStep) -> Result<TokenValue, ResolveError>;
 
+/// A lazily-built JS context shared across every step of a single resolve:
+/// all registered sources are parsed/evaluated into it once, and repeated
+/// invocations of the same caller expression are served from `memo` instead
+/// of re-evaluating the script.
+#[cfg(feature = "js")]
+struct JsRuntime {
+  ctx: JsContext,
+  memo: IndexMap<String, String>, // caller script -> JSON result string
+}
+
 pub struct TransformRegistry {
   builtins: IndexMap<String, TransformFn>,
   #[cfg(feature = "js")]
   js_funcs: IndexMap<String, String>, // name -> JS function source
+  #[cfg(feature = "js")]
+  js_runtime: std::cell::RefCell<Option<JsRuntime>>,
+  #[cfg(feature = "rhai")]
+  rhai_funcs: IndexMap<String, String>, // name -> Rhai function source
 }
 
 impl Default for TransformRegistry {
   fn default() -> Self {
     let mut builtins: IndexMap<String, TransformFn> = IndexMap::new();
     // Register built-in transforms
+    builtins.insert("literal".into(), builtin_literal as TransformFn);
     builtins.insert("alias".into(), builtin_alias as TransformFn);
     builtins.insert("multiply".into(), builtin_multiply as TransformFn);
     builtins.insert("add".into(), builtin_add as TransformFn);
     builtins.insert("subtract".into(), builtin_subtract as TransformFn);
     builtins.insert("divide".into(), builtin_divide as TransformFn);
+    builtins.insert("lighten".into(), builtin_lighten as TransformFn);
+    builtins.insert("darken".into(), builtin_darken as TransformFn);
+    builtins.insert("alpha".into(), builtin_alpha as TransformFn);
+    builtins.insert("mix".into(), builtin_mix as TransformFn);
+    builtins.insert("contrast".into(), builtin_contrast as TransformFn);
 
     TransformRegistry {
       builtins,
       #[cfg(feature = "js")]
       js_funcs: IndexMap::new(),
+      #[cfg(feature = "js")]
+      js_runtime: std::cell::RefCell::new(None),
+      #[cfg(feature = "rhai")]
+      rhai_funcs: IndexMap::new(),
     }
   }
 }
@@ -97,6 +134,14 @@ impl TransformRegistry {
   pub fn add_js_transform(&mut self, name: &str, source: &str) {
     self.js_funcs.insert(name.to_string(), source.to_string());
   }
+
+  /// Register a transform function written in Rhai, a small sandboxed
+  /// scripting language with no JS engine baggage - a better fit than Boa
+  /// for simple numeric/dimension token math.
+  #[cfg(feature = "rhai")]
+  pub fn add_rhai_transform(&mut self, name: &str, source: &str) {
+    self.rhai_funcs.insert(name.to_string(), source.to_string());
+  }
 }
 
 pub struct TransformContext<'a> {
@@ -104,6 +149,7 @@ pub struct TransformContext<'a> {
   pub tokens: &'a TokenSet,
   pub resolved: &'a mut TokenSet,
   pub stack: &'a mut Vec<String>,
+  pub palettes: &'a Palettes,
 }
 
 pub fn resolve_tokens(tokens: &TokenSet) -> Result<TokenSet, ResolveError> {
@@ -112,6 +158,13 @@ pub fn resolve_tokens(tokens: &TokenSet) -> Result<TokenSet, ResolveError> {
 }
 
 pub fn resolve_tokens_with_registry(tokens: &TokenSet, registry: &TransformRegistry) -> Result<TokenSet, ResolveError> {
+  resolve_tokens_with_palettes(tokens, registry, &Palettes::new())
+}
+
+pub fn resolve_tokens_with_palettes(tokens: &TokenSet, registry: &TransformRegistry, palettes: &Palettes) -> Result<TokenSet, ResolveError> {
+  // Each resolve starts without a JS runtime; the first JS step builds it.
+  #[cfg(feature = "js")]
+  registry.js_runtime.replace(None);
   let mut resolved: TokenSet = IndexMap::new();
   let mut stack: Vec<String> = Vec::new();
 
@@ -123,7 +176,7 @@ pub fn resolve_tokens_with_registry(tokens: &TokenSet, registry: &TransformRegis
     stack.clear();
     stack.push(key.clone());
 
-    let val = resolve_value(key, &token.value, tokens, &mut resolved, &mut stack, registry)
+    let val = resolve_value(key, &token.value, tokens, &mut resolved, &mut stack, registry, palettes)
       .map_err(|e| match e {
         ResolveError::CycleDetected(s) => ResolveError::CycleDetected(format!(
           "{} -> {}",
@@ -154,6 +207,7 @@ fn apply_transform_pipeline(
   resolved: &mut TokenSet,
   stack: &mut Vec<String>,
   registry: &TransformRegistry,
+  palettes: &Palettes,
 ) -> Result<TokenValue, ResolveError> {
   let mut current: Option<TokenValue> = None;
   for step in &expr.steps {
@@ -165,11 +219,40 @@ fn apply_transform_pipeline(
       resolved,
       stack,
       registry,
+      palettes,
     )?);
   }
   Ok(current.unwrap_or(TokenValue::Null))
 }
 
+fn resolve_palette_ref(
+  palette_name: &str,
+  tone: Option<u8>,
+  stack: &mut Vec<String>,
+  palettes: &Palettes,
+) -> Result<TokenValue, ResolveError> {
+  let stack_key = format!("$palette.{}", palette_name);
+  if stack.contains(&stack_key) {
+    return Err(ResolveError::CycleDetected(stack_key));
+  }
+
+  let config = palettes
+    .get(palette_name)
+    .ok_or_else(|| ResolveError::PaletteNotFound(palette_name.to_string()))?;
+
+  stack.push(stack_key);
+  let palette = crate::css::generate_palette_css(palette_name, config)
+    .map_err(|e| ResolveError::TransformFailed(format!("failed to generate palette '{}': {}", palette_name, e)))?;
+  stack.pop();
+
+  let tone = tone.unwrap_or_else(|| palette.key_tone());
+  let oklch = palette
+    .oklch_for_tone(tone)
+    .ok_or_else(|| ResolveError::ToneNotFound(tone, palette_name.to_string()))?;
+
+  Ok(TokenValue::Color(oklch))
+}
+
 fn resolve_value(
   name: &str,
   val: &TokenValue,
@@ -177,9 +260,11 @@ fn resolve_value(
   resolved: &mut TokenSet,
   stack: &mut Vec<String>,
   registry: &TransformRegistry,
+  palettes: &Palettes,
 ) -> Result<TokenValue, ResolveError> {
   match val {
-    TokenValue::Transform(expr) => apply_transform_pipeline(name, expr, tokens, resolved, stack, registry),
+    TokenValue::Transform(expr) => apply_transform_pipeline(name, expr, tokens, resolved, stack, registry, palettes),
+    TokenValue::PaletteRef { palette, tone } => resolve_palette_ref(palette, *tone, stack, palettes),
     TokenValue::Reference(target_path) => {
       if !tokens.contains_key(target_path) {
         return Err(ResolveError::TokenNotFound(format!(
@@ -187,14 +272,17 @@ fn resolve_value(
           target_path, name
         )));
       }
-      let css_var = format!("--{}", target_path.replace('.', "-"));
-      Ok(TokenValue::String(format!("var({})", css_var)))
+      // Left unresolved here (not baked into a `var(...)` string) so
+      // `emit_css` can render it with the *target*'s own `CssKeyOptions`
+      // instead of a hardcoded dash-separated name - see its
+      // `Reference | Alias => css_var(target, opts)` arm.
+      Ok(TokenValue::Reference(target_path.clone()))
     }
-    TokenValue::Alias(target_path) => resolve_alias(name, target_path, tokens, resolved, stack, registry),
+    TokenValue::Alias(target_path) => resolve_alias(name, target_path, tokens, resolved, stack, registry, palettes),
     TokenValue::Object(map) => {
       let mut new_map = IndexMap::new();
       for (k, v) in map.iter() {
-        let rv = resolve_value(name, v, tokens, resolved, stack, registry)?;
+        let rv = resolve_value(name, v, tokens, resolved, stack, registry, palettes)?;
         new_map.insert(k.clone(), rv);
       }
       Ok(TokenValue::Object(new_map))
@@ -222,6 +310,7 @@ fn resolve_alias(
   resolved: &mut TokenSet,
   stack: &mut Vec<String>,
   registry: &TransformRegistry,
+  palettes: &Palettes,
 ) -> Result<TokenValue, ResolveError> {
   if stack.contains(&target_path.to_string()) {
     return Err(ResolveError::CycleDetected(target_path.to_string()));
@@ -243,6 +332,7 @@ fn resolve_alias(
     resolved,
     stack,
     registry,
+    palettes,
   )?;
   stack.pop();
 
@@ -273,6 +363,16 @@ fn css_calc(expr_lhs: &str, op: &str, expr_rhs: &str) -> String {
   format!("calc({} {} {})", expr_lhs, op, expr_rhs)
 }
 
+/// Default dash-separated `var(--a-b-c)` rendering for a `Reference` used as
+/// an operand inside an arithmetic transform step. Unlike the top-level
+/// `Reference` a token resolves to (which stays unbaked for `emit_css` to
+/// render per-target), a reference embedded in a `calc()` expression has to
+/// become a concrete string right here, so it falls back to this default
+/// naming rather than any particular target's `CssKeyOptions`.
+fn reference_to_calc_var(target_path: &str) -> String {
+  format!("var(--{})", target_path.replace('.', "-"))
+}
+
 // ---- Built-in transform implementations ----
 fn builtin_alias(
   _registry: &TransformRegistry,
@@ -289,7 +389,7 @@ fn builtin_alias(
       _ => None,
     })
     .ok_or_else(|| ResolveError::InvalidTransform("alias requires string arg".into()))?;
-  resolve_alias(ctx.name, &target, ctx.tokens, ctx.resolved, ctx.stack, _registry)
+  resolve_alias(ctx.name, &target, ctx.tokens, ctx.resolved, ctx.stack, _registry, ctx.palettes)
 }
 
 fn builtin_multiply(
@@ -308,6 +408,7 @@ fn builtin_multiply(
     Some(TokenValue::Dimension { value, unit }) => Ok(TokenValue::Dimension { value: value * factor, unit }),
     Some(TokenValue::String(s)) if is_css_calcable_string(&s) => Ok(TokenValue::String(css_calc(&s, "*", &fmt_num(factor)))),
     Some(TokenValue::String(s)) => Ok(TokenValue::String(css_calc(&s, "*", &fmt_num(factor)))),
+    Some(TokenValue::Reference(r)) => Ok(TokenValue::String(css_calc(&reference_to_calc_var(&r), "*", &fmt_num(factor)))),
     _ => Err(ResolveError::TransformFailed("multiply expects number/dimension/string input".into())),
   }
 }
@@ -342,6 +443,10 @@ fn builtin_add(
       let rhs = if let Some(u) = add_unit_opt { format!("{}{}", fmt_num(add_val), u) } else { fmt_num(add_val) };
       Ok(TokenValue::String(css_calc(&s, "+", &rhs)))
     }
+    Some(TokenValue::Reference(r)) => {
+      let rhs = if let Some(u) = add_unit_opt { format!("{}{}", fmt_num(add_val), u) } else { fmt_num(add_val) };
+      Ok(TokenValue::String(css_calc(&reference_to_calc_var(&r), "+", &rhs)))
+    }
     _ => Err(ResolveError::TransformFailed("add expects number/dimension/string input".into())),
   }
 }
@@ -376,10 +481,29 @@ fn builtin_subtract(
       let rhs = if let Some(u) = sub_unit_opt { format!("{}{}", fmt_num(sub_val), u) } else { fmt_num(sub_val) };
       Ok(TokenValue::String(css_calc(&s, "-", &rhs)))
     }
+    Some(TokenValue::Reference(r)) => {
+      let rhs = if let Some(u) = sub_unit_opt { format!("{}{}", fmt_num(sub_val), u) } else { fmt_num(sub_val) };
+      Ok(TokenValue::String(css_calc(&reference_to_calc_var(&r), "-", &rhs)))
+    }
     _ => Err(ResolveError::TransformFailed("subtract expects number/dimension/string input".into())),
   }
 }
 
+/// Yields its sole arg verbatim, ignoring any incoming `input` - the seed
+/// step for a pipeline built from a parsed literal (number/dimension).
+fn builtin_literal(
+  _registry: &TransformRegistry,
+  _ctx: &mut TransformContext,
+  _input: Option<TokenValue>,
+  step: &TransformStep,
+) -> Result<TokenValue, ResolveError> {
+  step
+    .args
+    .get(0)
+    .cloned()
+    .ok_or_else(|| ResolveError::InvalidTransform("literal requires a value arg".into()))
+}
+
 fn builtin_divide(
   _registry: &TransformRegistry,
   _ctx: &mut TransformContext,
@@ -399,10 +523,280 @@ fn builtin_divide(
     Some(TokenValue::Dimension { value, unit }) => Ok(TokenValue::Dimension { value: value / divisor, unit }),
     Some(TokenValue::String(s)) if is_css_calcable_string(&s) => Ok(TokenValue::String(css_calc(&s, "/", &fmt_num(divisor)))),
     Some(TokenValue::String(s)) => Ok(TokenValue::String(css_calc(&s, "/", &fmt_num(divisor)))),
+    Some(TokenValue::Reference(r)) => Ok(TokenValue::String(css_calc(&reference_to_calc_var(&r), "/", &fmt_num(divisor)))),
     _ => Err(ResolveError::TransformFailed("divide expects number/dimension/string input".into())),
   }
 }
 
+// ---- Built-in color transforms (lighten/darken/alpha/mix/contrast) ----
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rgba {
+  r: u8,
+  g: u8,
+  b: u8,
+  a: f64,
+}
+
+/// Parse `#rgb`, `#rrggbb`, `#rgba`, `#rrggbbaa`, `rgb(...)`, or `rgba(...)`
+/// into an `Rgba`. Anything else is a `TransformFailed`, not a panic.
+fn parse_color(s: &str) -> Result<Rgba, ResolveError> {
+  let s = s.trim();
+
+  if let Some(hex) = s.strip_prefix('#') {
+    let expand = |c: char| -> Option<u8> { u8::from_str_radix(&c.to_string().repeat(2), 16).ok() };
+    let hex_pair = |pair: &str| -> Option<u8> { u8::from_str_radix(pair, 16).ok() };
+
+    return match hex.len() {
+      3 => {
+        let chars: Vec<char> = hex.chars().collect();
+        match (expand(chars[0]), expand(chars[1]), expand(chars[2])) {
+          (Some(r), Some(g), Some(b)) => Ok(Rgba { r, g, b, a: 1.0 }),
+          _ => Err(ResolveError::TransformFailed(format!("unparseable color: '{}'", s))),
+        }
+      }
+      4 => {
+        let chars: Vec<char> = hex.chars().collect();
+        match (expand(chars[0]), expand(chars[1]), expand(chars[2]), expand(chars[3])) {
+          (Some(r), Some(g), Some(b), Some(a)) => Ok(Rgba { r, g, b, a: a as f64 / 255.0 }),
+          _ => Err(ResolveError::TransformFailed(format!("unparseable color: '{}'", s))),
+        }
+      }
+      6 => match (hex_pair(&hex[0..2]), hex_pair(&hex[2..4]), hex_pair(&hex[4..6])) {
+        (Some(r), Some(g), Some(b)) => Ok(Rgba { r, g, b, a: 1.0 }),
+        _ => Err(ResolveError::TransformFailed(format!("unparseable color: '{}'", s))),
+      },
+      8 => match (hex_pair(&hex[0..2]), hex_pair(&hex[2..4]), hex_pair(&hex[4..6]), hex_pair(&hex[6..8])) {
+        (Some(r), Some(g), Some(b), Some(a)) => Ok(Rgba { r, g, b, a: a as f64 / 255.0 }),
+        _ => Err(ResolveError::TransformFailed(format!("unparseable color: '{}'", s))),
+      },
+      _ => Err(ResolveError::TransformFailed(format!("unparseable color: '{}'", s))),
+    };
+  }
+
+  if let Some(inner) = s.strip_prefix("rgba(").or_else(|| s.strip_prefix("rgb(")) {
+    let inner = inner.strip_suffix(')').ok_or_else(|| ResolveError::TransformFailed(format!("unparseable color: '{}'", s)))?;
+    let parts: Vec<&str> = inner.split(',').map(|p| p.trim()).collect();
+    if parts.len() != 3 && parts.len() != 4 {
+      return Err(ResolveError::TransformFailed(format!("unparseable color: '{}'", s)));
+    }
+    let channel = |p: &str| -> Result<u8, ResolveError> {
+      p.trim_end_matches('%')
+        .parse::<f64>()
+        .map(|n| n.round().clamp(0.0, 255.0) as u8)
+        .map_err(|_| ResolveError::TransformFailed(format!("unparseable color: '{}'", s)))
+    };
+    let r = channel(parts[0])?;
+    let g = channel(parts[1])?;
+    let b = channel(parts[2])?;
+    let a = if parts.len() == 4 {
+      parts[3].parse::<f64>().map_err(|_| ResolveError::TransformFailed(format!("unparseable color: '{}'", s)))?
+    } else {
+      1.0
+    };
+    return Ok(Rgba { r, g, b, a: a.clamp(0.0, 1.0) });
+  }
+
+  Err(ResolveError::TransformFailed(format!("unparseable color: '{}'", s)))
+}
+
+impl Rgba {
+  fn to_css_string(self) -> String {
+    if (self.a - 1.0).abs() < f64::EPSILON {
+      format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    } else {
+      format!("rgba({}, {}, {}, {})", self.r, self.g, self.b, fmt_num(self.a))
+    }
+  }
+
+  fn to_hsl(self) -> (f64, f64, f64) {
+    let r = self.r as f64 / 255.0;
+    let g = self.g as f64 / 255.0;
+    let b = self.b as f64 / 255.0;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+
+    if (max - min).abs() < f64::EPSILON {
+      return (0.0, 0.0, l);
+    }
+
+    let d = max - min;
+    let s = if l > 0.5 { d / (2.0 - max - min) } else { d / (max + min) };
+    let h = if max == r {
+      (g - b) / d + if g < b { 6.0 } else { 0.0 }
+    } else if max == g {
+      (b - r) / d + 2.0
+    } else {
+      (r - g) / d + 4.0
+    };
+
+    (h / 6.0, s, l)
+  }
+
+  fn from_hsl(h: f64, s: f64, l: f64, a: f64) -> Rgba {
+    let hue_to_rgb = |p: f64, q: f64, mut t: f64| -> f64 {
+      if t < 0.0 {
+        t += 1.0;
+      }
+      if t > 1.0 {
+        t -= 1.0;
+      }
+      if t < 1.0 / 6.0 {
+        return p + (q - p) * 6.0 * t;
+      }
+      if t < 1.0 / 2.0 {
+        return q;
+      }
+      if t < 2.0 / 3.0 {
+        return p + (q - p) * (2.0 / 3.0 - t) * 6.0;
+      }
+      p
+    };
+
+    let (r, g, b) = if s.abs() < f64::EPSILON {
+      (l, l, l)
+    } else {
+      let q = if l < 0.5 { l * (1.0 + s) } else { l + s - l * s };
+      let p = 2.0 * l - q;
+      (hue_to_rgb(p, q, h + 1.0 / 3.0), hue_to_rgb(p, q, h), hue_to_rgb(p, q, h - 1.0 / 3.0))
+    };
+
+    Rgba {
+      r: (r * 255.0).round().clamp(0.0, 255.0) as u8,
+      g: (g * 255.0).round().clamp(0.0, 255.0) as u8,
+      b: (b * 255.0).round().clamp(0.0, 255.0) as u8,
+      a: a.clamp(0.0, 1.0),
+    }
+  }
+
+  /// WCAG relative luminance, used by `builtin_contrast`.
+  fn relative_luminance(self) -> f64 {
+    let channel = |c: u8| -> f64 {
+      let c = c as f64 / 255.0;
+      if c <= 0.03928 {
+        c / 12.92
+      } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+      }
+    };
+    0.2126 * channel(self.r) + 0.7152 * channel(self.g) + 0.0722 * channel(self.b)
+  }
+}
+
+fn contrast_ratio(a: Rgba, b: Rgba) -> f64 {
+  let (l1, l2) = (a.relative_luminance(), b.relative_luminance());
+  let (lighter, darker) = if l1 >= l2 { (l1, l2) } else { (l2, l1) };
+  (lighter + 0.05) / (darker + 0.05)
+}
+
+fn color_input(input: &Option<TokenValue>, label: &str) -> Result<Rgba, ResolveError> {
+  match input {
+    Some(TokenValue::Color(s)) | Some(TokenValue::String(s)) => parse_color(s),
+    _ => Err(ResolveError::TransformFailed(format!("{} expects a color input", label))),
+  }
+}
+
+fn color_arg(step: &TransformStep, index: usize, label: &str) -> Result<Rgba, ResolveError> {
+  match step.args.get(index) {
+    Some(TokenValue::Color(s)) | Some(TokenValue::String(s)) => parse_color(s),
+    _ => Err(ResolveError::InvalidTransform(format!("{} requires a color arg", label))),
+  }
+}
+
+fn percent_arg(step: &TransformStep, index: usize, label: &str) -> Result<f64, ResolveError> {
+  match step.args.get(index) {
+    Some(TokenValue::Number(n)) => Ok(*n),
+    _ => Err(ResolveError::InvalidTransform(format!("{} requires a percent number arg", label))),
+  }
+}
+
+/// Lighten by `args[0]` percentage points (0-100) in HSL space.
+fn builtin_lighten(
+  _registry: &TransformRegistry,
+  _ctx: &mut TransformContext,
+  input: Option<TokenValue>,
+  step: &TransformStep,
+) -> Result<TokenValue, ResolveError> {
+  let color = color_input(&input, "lighten")?;
+  let amount = percent_arg(step, 0, "lighten")?;
+  let (h, s, l) = color.to_hsl();
+  let lightened = Rgba::from_hsl(h, s, (l + amount / 100.0).clamp(0.0, 1.0), color.a);
+  Ok(TokenValue::Color(lightened.to_css_string()))
+}
+
+/// Darken by `args[0]` percentage points (0-100) in HSL space.
+fn builtin_darken(
+  _registry: &TransformRegistry,
+  _ctx: &mut TransformContext,
+  input: Option<TokenValue>,
+  step: &TransformStep,
+) -> Result<TokenValue, ResolveError> {
+  let color = color_input(&input, "darken")?;
+  let amount = percent_arg(step, 0, "darken")?;
+  let (h, s, l) = color.to_hsl();
+  let darkened = Rgba::from_hsl(h, s, (l - amount / 100.0).clamp(0.0, 1.0), color.a);
+  Ok(TokenValue::Color(darkened.to_css_string()))
+}
+
+/// Set opacity to `args[0]` (0.0-1.0).
+fn builtin_alpha(
+  _registry: &TransformRegistry,
+  _ctx: &mut TransformContext,
+  input: Option<TokenValue>,
+  step: &TransformStep,
+) -> Result<TokenValue, ResolveError> {
+  let color = color_input(&input, "alpha")?;
+  let alpha = percent_arg(step, 0, "alpha")?;
+  Ok(TokenValue::Color(Rgba { a: alpha.clamp(0.0, 1.0), ..color }.to_css_string()))
+}
+
+/// Channel-wise interpolation with `args[0]` (the other color) weighted by
+/// `args[1]` (0.0-1.0, defaulting to an even 0.5 mix).
+fn builtin_mix(
+  _registry: &TransformRegistry,
+  _ctx: &mut TransformContext,
+  input: Option<TokenValue>,
+  step: &TransformStep,
+) -> Result<TokenValue, ResolveError> {
+  let a = color_input(&input, "mix")?;
+  let b = color_arg(step, 0, "mix")?;
+  let ratio = match step.args.get(1) {
+    Some(TokenValue::Number(n)) => n.clamp(0.0, 1.0),
+    None => 0.5,
+    _ => return Err(ResolveError::InvalidTransform("mix ratio must be a number".into())),
+  };
+
+  let lerp = |x: u8, y: u8| -> u8 { (x as f64 + (y as f64 - x as f64) * ratio).round().clamp(0.0, 255.0) as u8 };
+  let mixed = Rgba {
+    r: lerp(a.r, b.r),
+    g: lerp(a.g, b.g),
+    b: lerp(a.b, b.b),
+    a: a.a + (b.a - a.a) * ratio,
+  };
+  Ok(TokenValue::Color(mixed.to_css_string()))
+}
+
+/// Pick whichever of `args[0]`/`args[1]` has the higher WCAG contrast ratio
+/// against the input color - e.g. choosing a light or dark "on-color".
+fn builtin_contrast(
+  _registry: &TransformRegistry,
+  _ctx: &mut TransformContext,
+  input: Option<TokenValue>,
+  step: &TransformStep,
+) -> Result<TokenValue, ResolveError> {
+  let base = color_input(&input, "contrast")?;
+  let candidate_a = color_arg(step, 0, "contrast")?;
+  let candidate_b = color_arg(step, 1, "contrast")?;
+
+  let picked = if contrast_ratio(base, candidate_a) >= contrast_ratio(base, candidate_b) {
+    candidate_a
+  } else {
+    candidate_b
+  };
+  Ok(TokenValue::Color(picked.to_css_string()))
+}
+
 #[cfg(feature = "js")]
 fn tokenvalue_to_json(val: &TokenValue) -> serde_json::Value {
   match val {
@@ -453,30 +847,43 @@ fn json_to_tokenvalue_value(val: serde_json::Value) -> TokenValue {
 
 #[cfg(feature = "js")]
 fn run_js_transform(
+  registry: &TransformRegistry,
   name: &str,
-  source: &str,
   token_name: &str,
   input: Option<TokenValue>,
   step: &TransformStep,
-  _tokens: &TokenSet,
-  _resolved: &mut TokenSet,
-  _stack: &mut Vec<String>,
 ) -> Result<TokenValue, ResolveError> {
+  let mut runtime_slot = registry.js_runtime.borrow_mut();
+  if runtime_slot.is_none() {
+    let mut ctx = JsContext::default();
+    for src in registry.js_funcs.values() {
+      ctx
+        .eval(Source::from_bytes(src.as_bytes()))
+        .map_err(|e| ResolveError::TransformFailed(format!("JS init error: {}", e)))?;
+    }
+    *runtime_slot = Some(JsRuntime { ctx, memo: IndexMap::new() });
+  }
+  let runtime = runtime_slot.as_mut().unwrap();
+
   let input_json = tokenvalue_to_json(&input.unwrap_or(TokenValue::Null)).to_string();
   let args_json = serde_json::to_string(&step.args).unwrap_or_else(|_| "[]".into());
   let ctx_json = serde_json::json!({"token": token_name}).to_string();
   let call_script = format!(
-    "{}\n(function(){{ const fn = (typeof {}==='function'? {} : globalThis[{}]); if(!fn) throw new Error('transform not found'); return JSON.stringify(fn({}, {}, {})); }})()",
-    source,
-    name,
-    name,
+    "(function(){{ const fn = globalThis[{}]; if(!fn) throw new Error('transform not found'); return JSON.stringify(fn({}, {}, {})); }})()",
     serde_json::to_string(name).unwrap(),
     input_json,
     args_json,
     ctx_json
   );
-  let mut ctx = JsContext::default();
-  let result = ctx
+
+  if let Some(cached) = runtime.memo.get(&call_script) {
+    let val: serde_json::Value = serde_json::from_str(cached)
+      .map_err(|e| ResolveError::TransformFailed(format!("JS returned invalid JSON: {}", e)))?;
+    return Ok(json_to_tokenvalue_value(val));
+  }
+
+  let result = runtime
+    .ctx
     .eval(Source::from_bytes(&call_script))
     .map_err(|e| ResolveError::TransformFailed(format!("JS eval error in '{}': {}", name, e)))?;
   let s = result
@@ -484,39 +891,159 @@ fn run_js_transform(
     .ok_or_else(|| ResolveError::TransformFailed("JS transform did not return a JSON string".into()))?
     .to_std_string()
     .map_err(|_| ResolveError::TransformFailed("failed to convert JS string".into()))?;
+
   let val: serde_json::Value = serde_json::from_str(&s)
     .map_err(|e| ResolveError::TransformFailed(format!("JS returned invalid JSON: {}", e)))?;
+  runtime.memo.insert(call_script, s);
   Ok(json_to_tokenvalue_value(val))
 }
-fn apply_transform_step(
+#[cfg(feature = "rhai")]
+fn tokenvalue_to_rhai(val: &TokenValue) -> rhai::Dynamic {
+  match val {
+    TokenValue::Null => rhai::Dynamic::UNIT,
+    TokenValue::Bool(b) => (*b).into(),
+    TokenValue::Number(n) => (*n).into(),
+    TokenValue::String(s) | TokenValue::Color(s) | TokenValue::Alias(s) | TokenValue::Reference(s) => s.clone().into(),
+    TokenValue::Dimension { value, unit } => {
+      let mut map = rhai::Map::new();
+      map.insert("value".into(), (*value).into());
+      map.insert("unit".into(), unit.clone().into());
+      map.into()
+    }
+    TokenValue::Object(obj) => {
+      let mut map = rhai::Map::new();
+      for (k, v) in obj.iter() {
+        map.insert(k.as_str().into(), tokenvalue_to_rhai(v));
+      }
+      map.into()
+    }
+    TokenValue::PaletteRef { palette, .. } => palette.clone().into(),
+    TokenValue::Transform(_) => rhai::Dynamic::UNIT,
+  }
+}
+
+#[cfg(feature = "rhai")]
+fn rhai_to_tokenvalue(val: rhai::Dynamic) -> TokenValue {
+  if val.is_unit() {
+    return TokenValue::Null;
+  }
+  if let Some(b) = val.clone().try_cast::<bool>() {
+    return TokenValue::Bool(b);
+  }
+  if let Some(n) = val.clone().try_cast::<f64>() {
+    return TokenValue::Number(n);
+  }
+  if let Some(i) = val.clone().try_cast::<i64>() {
+    return TokenValue::Number(i as f64);
+  }
+  if let Some(s) = val.clone().try_cast::<String>() {
+    return TokenValue::String(s);
+  }
+  if let Some(map) = val.clone().try_cast::<rhai::Map>() {
+    if let (Some(v), Some(u)) = (map.get("value").cloned(), map.get("unit").cloned()) {
+      if let (Some(value), Some(unit)) = (v.try_cast::<f64>(), u.try_cast::<String>()) {
+        return TokenValue::Dimension { value, unit };
+      }
+    }
+    let mut out = IndexMap::new();
+    for (k, v) in map.into_iter() {
+      out.insert(k.to_string(), rhai_to_tokenvalue(v));
+    }
+    return TokenValue::Object(out);
+  }
+  if let Some(arr) = val.clone().try_cast::<rhai::Array>() {
+    let mut out = IndexMap::new();
+    for (i, v) in arr.into_iter().enumerate() {
+      out.insert(i.to_string(), rhai_to_tokenvalue(v));
+    }
+    return TokenValue::Object(out);
+  }
+  TokenValue::Null
+}
+
+#[cfg(feature = "rhai")]
+fn run_rhai_transform(
+  name: &str,
+  sources: &IndexMap<String, String>,
+  token_name: &str,
+  input: Option<TokenValue>,
+  step: &TransformStep,
+) -> Result<TokenValue, ResolveError> {
+  let engine = RhaiEngine::new();
+  let combined = sources.values().cloned().collect::<Vec<_>>().join("\n\n");
+  let ast = engine
+    .compile(&combined)
+    .map_err(|e| ResolveError::TransformFailed(format!("Rhai compile error: {}", e)))?;
+
+  let input_dyn = tokenvalue_to_rhai(&input.unwrap_or(TokenValue::Null));
+  let args_dyn: rhai::Array = step.args.iter().map(tokenvalue_to_rhai).collect();
+  let mut ctx_map = rhai::Map::new();
+  ctx_map.insert("token".into(), token_name.to_string().into());
+
+  let result: rhai::Dynamic = engine
+    .call_fn(&mut RhaiScope::new(), &ast, name, (input_dyn, args_dyn, rhai::Dynamic::from_map(ctx_map)))
+    .map_err(|e| ResolveError::TransformFailed(format!("Rhai eval error in '{}': {}", name, e)))?;
+
+  Ok(rhai_to_tokenvalue(result))
+}
+
+/// Resolves any `TokenValue::Transform` args in place, evaluating the nested
+/// sub-pipeline first - this is how a parenthesized group like `{a} * (2 + 1)`
+/// or an alias operand like `{a} + {b}` reaches a builtin as a plain
+/// `Number`/`Dimension`, the only shapes the arithmetic builtins accept.
+fn resolve_step_args(
   name: &str,
   step: &TransformStep,
+  tokens: &TokenSet,
+  resolved: &mut TokenSet,
+  stack: &mut Vec<String>,
+  registry: &TransformRegistry,
+  palettes: &Palettes,
+) -> Result<TransformStep, ResolveError> {
+  let mut args = Vec::with_capacity(step.args.len());
+  for arg in &step.args {
+    let resolved_arg = match arg {
+      TokenValue::Transform(expr) => apply_transform_pipeline(name, expr, tokens, resolved, stack, registry, palettes)?,
+      other => other.clone(),
+    };
+    args.push(resolved_arg);
+  }
+  Ok(TransformStep { r#type: step.r#type.clone(), args })
+}
+
+fn apply_transform_step(
+  name: &str,
+  raw_step: &TransformStep,
   input: Option<TokenValue>,
   tokens: &TokenSet,
   resolved: &mut TokenSet,
   stack: &mut Vec<String>,
   registry: &TransformRegistry,
+  palettes: &Palettes,
 ) -> Result<TokenValue, ResolveError> {
+  let step = &resolve_step_args(name, raw_step, tokens, resolved, stack, registry, palettes)?;
+
   // Dispatch to built-in or JS transform by name
   if let Some(func) = registry.get_builtin(step.r#type.as_str()) {
-    let mut ctx = TransformContext { name, tokens, resolved, stack };
+    let mut ctx = TransformContext { name, tokens, resolved, stack, palettes };
     return func(registry, &mut ctx, input, step);
   }
 
   #[cfg(feature = "js")]
   {
-    // Evaluate all registered JS sources and then call the function named by step.r#type.
-    // This allows plugins to register multiple transforms in a single file.
+    // All registered JS sources are compiled into a shared context once (see
+    // `JsRuntime`); this only evaluates a small caller expression per step.
     if !registry.js_funcs.is_empty() {
-      // Concatenate sources and execute once
-      let combined = registry
-        .js_funcs
-        .values()
-        .cloned()
-        .collect::<Vec<_>>()
-        .join("\n\n");
-      // Try to invoke the target function
-      if let Ok(rv) = run_js_transform(step.r#type.as_str(), &combined, name, input.clone(), step, tokens, resolved, stack) {
+      if let Ok(rv) = run_js_transform(registry, step.r#type.as_str(), name, input.clone(), step) {
+        return Ok(rv);
+      }
+    }
+  }
+
+  #[cfg(feature = "rhai")]
+  {
+    if !registry.rhai_funcs.is_empty() {
+      if let Ok(rv) = run_rhai_transform(step.r#type.as_str(), &registry.rhai_funcs, name, input.clone(), step) {
         return Ok(rv);
       }
     }
@@ -525,81 +1052,1355 @@ fn apply_transform_step(
   Err(ResolveError::InvalidTransform(format!("unknown transform: {}", step.r#type)))
 }
 
-pub fn to_css_custom_properties(tokens: &TokenSet) -> IndexMap<String, String> {
-  let mut map = IndexMap::new();
-  for (key, token) in tokens.iter() {
-    let css_name = format!("--{}", key.replace('.', "-"));
-    let css_value = token_value_to_string(&token.value);
-    map.insert(css_name, css_value);
-  }
-  map
+/// Looks like a color literal (`#rgb`/`#rrggbb`, `rgb(...)`/`rgba(...)`) rather
+/// than a plain string, for the purposes of flattening an external JSON tree.
+fn looks_like_color(s: &str) -> bool {
+  s.starts_with('#') || s.starts_with("rgb(") || s.starts_with("rgba(")
 }
 
-pub fn token_value_to_string(value: &TokenValue) -> String {
-  match value {
-    TokenValue::String(s) => s.clone(),
-    TokenValue::Number(n) => {
-      if (n.fract()).abs() < std::f64::EPSILON {
-        format!("{:.0}", n)
-      } else {
-        n.to_string()
+/// Convert a `serde_json::Value` leaf into a `TokenValue`, recognizing the
+/// `{ "type": "dimension", "value", "unit" }` convention used elsewhere in
+/// this crate and treating color-like strings as `TokenValue::Color`.
+fn json_leaf_to_tokenvalue(val: &serde_json::Value) -> TokenValue {
+  match val {
+    serde_json::Value::Null => TokenValue::Null,
+    serde_json::Value::Bool(b) => TokenValue::Bool(*b),
+    serde_json::Value::Number(n) => TokenValue::Number(n.as_f64().unwrap_or(0.0)),
+    serde_json::Value::String(s) if looks_like_color(s) => TokenValue::Color(s.clone()),
+    serde_json::Value::String(s) => TokenValue::String(s.clone()),
+    serde_json::Value::Array(arr) => {
+      let mut map = IndexMap::new();
+      for (i, item) in arr.iter().enumerate() {
+        map.insert(i.to_string(), json_leaf_to_tokenvalue(item));
       }
+      TokenValue::Object(map)
     }
-    TokenValue::Bool(b) => b.to_string(),
-    TokenValue::Object(obj) => serde_json::to_string(obj).unwrap_or_else(|_| String::from("{}")),
-    TokenValue::Alias(a) => format!("alias({})", a),
-    TokenValue::Reference(r) => format!("reference({})", r),
-    TokenValue::Color(c) => c.clone(),
-    TokenValue::Dimension { value, unit } => {
-      if (value.fract()).abs() < std::f64::EPSILON {
-        format!("{:.0}{}", value, unit)
-      } else {
-        format!("{}{}", value, unit)
+    serde_json::Value::Object(obj) => {
+      if let (Some(serde_json::Value::String(t)), Some(value)) = (obj.get("type"), obj.get("value")) {
+        if t == "dimension" {
+          if let Some(unit) = obj.get("unit").and_then(|u| u.as_str()) {
+            return TokenValue::Dimension { value: value.as_f64().unwrap_or(0.0), unit: unit.to_string() };
+          }
+        }
+      }
+      let mut map = IndexMap::new();
+      for (k, v) in obj.iter() {
+        map.insert(k.clone(), json_leaf_to_tokenvalue(v));
       }
+      TokenValue::Object(map)
     }
-    TokenValue::Transform(_) => "unresolved-transform".to_string(),
-    TokenValue::Null => String::from("null"),
   }
 }
 
-/// Build a CSS stylesheet with a selector (e.g., ":root") and optional prefix for variable names.
-pub fn to_css_stylesheet(tokens: &TokenSet, selector: &str, prefix: Option<&str>) -> String {
-  let mut out = String::new();
-  out.push_str(selector);
-  out.push_str(" {\n");
-  for (key, token) in tokens.iter() {
-    let var_name = match prefix {
-      Some(p) if !p.is_empty() => format!("--{}-{}", p, key.replace('.', "-")),
-      _ => format!("--{}", key.replace('.', "-")),
-    };
-    let value = token_value_to_string(&token.value);
-    out.push_str("  ");
-    out.push_str(&var_name);
-    out.push_str(": ");
-    out.push_str(&value);
-    out.push_str(";\n");
+// ---- DTCG ($value/$type/$description/$extensions) JSON import ----
+
+/// A DTCG alias value is a bare `{dotted.path}` string - distinct from the
+/// `{ "alias": "..." }`/`TokenValue::Alias` shape used elsewhere in this
+/// crate, but it means the same thing once recognized.
+fn dtcg_alias_target(s: &str) -> Option<String> {
+  let trimmed = s.trim();
+  if trimmed.len() > 2 && trimmed.starts_with('{') && trimmed.ends_with('}') {
+    Some(trimmed[1..trimmed.len() - 1].trim().to_string())
+  } else {
+    None
   }
-  out.push('}');
-  out
 }
 
-/// Produce a mapping from token path (e.g., "spacing.base") to resolved string value.
-pub fn to_resolved_string_map(tokens: &TokenSet) -> IndexMap<String, String> {
-  let mut map = IndexMap::new();
-  for (key, token) in tokens.iter() {
-    map.insert(key.clone(), token_value_to_string(&token.value));
+/// Parse a DTCG dimension string like `4px` or `1.5rem` into its
+/// numeric value and unit.
+fn parse_dimension_string(s: &str) -> Option<TokenValue> {
+  let trimmed = s.trim();
+  let split_at = trimmed.find(|c: char| !c.is_ascii_digit() && c != '.' && c != '-')?;
+  let (num, unit) = trimmed.split_at(split_at);
+  if unit.is_empty() {
+    return None;
   }
-  map
+  let value: f64 = num.parse().ok()?;
+  Some(TokenValue::Dimension { value, unit: unit.to_string() })
 }
 
-pub fn merge_token_sets(base: &TokenSet, overrides: &TokenSet) -> TokenSet {
-  let mut out = base.clone();
+/// Canonicalize a color literal to `to_css_string`'s hex/rgba form. Anything
+/// `parse_color` doesn't understand (an `hsl()`/`hsla()` string, a CSS
+/// keyword like `transparent`) is left as-is rather than discarded.
+fn normalize_color_string(s: &str) -> String {
+  parse_color(s).map(|c| c.to_css_string()).unwrap_or_else(|_| s.to_string())
+}
+
+/// Parse a DTCG duration (`"200ms"`, `"1.5s"`) into milliseconds.
+fn normalize_duration_ms(s: &str) -> Option<f64> {
+  let trimmed = s.trim();
+  if let Some(n) = trimmed.strip_suffix("ms") {
+    n.parse::<f64>().ok()
+  } else {
+    trimmed.strip_suffix('s').and_then(|n| n.parse::<f64>().ok()).map(|secs| secs * 1000.0)
+  }
+}
+
+/// Map a `fontWeight` value to its numeric CSS weight - the four keywords
+/// this crate treats as valid in `validate_font_weight_value`, or a number
+/// passed through unchanged.
+fn normalize_font_weight(value: &serde_json::Value) -> Option<f64> {
+  match value {
+    serde_json::Value::Number(n) => n.as_f64(),
+    serde_json::Value::String(s) => match s.to_lowercase().as_str() {
+      "normal" => Some(400.0),
+      "bold" => Some(700.0),
+      "bolder" => Some(900.0),
+      "lighter" => Some(300.0),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// Shared by the `dimension` leaf case and every composite type with a
+/// dimension-valued field (`shadow.blur`, `typography.fontSize`, `border.width`).
+fn normalize_dimension_to_tokenvalue(value: &serde_json::Value) -> TokenValue {
+  match value {
+    serde_json::Value::String(s) => parse_dimension_string(s).unwrap_or_else(|| TokenValue::String(s.clone())),
+    serde_json::Value::Object(obj) => {
+      match (obj.get("value").and_then(|v| v.as_f64()), obj.get("unit").and_then(|v| v.as_str())) {
+        (Some(value), Some(unit)) => TokenValue::Dimension { value, unit: unit.to_string() },
+        _ => json_leaf_to_tokenvalue(value),
+      }
+    }
+    other => json_leaf_to_tokenvalue(other),
+  }
+}
+
+fn normalize_shadow_entry_value(value: &serde_json::Value) -> TokenValue {
+  let serde_json::Value::Object(obj) = value else {
+    return json_leaf_to_tokenvalue(value);
+  };
+
+  let mut out = IndexMap::new();
+  for field in ["offsetX", "offsetY", "blur", "spread"] {
+    if let Some(v) = obj.get(field) {
+      out.insert(field.to_string(), normalize_dimension_to_tokenvalue(v));
+    }
+  }
+  if let Some(color) = obj.get("color").and_then(|v| v.as_str()) {
+    out.insert("color".to_string(), TokenValue::Color(normalize_color_string(color)));
+  }
+  TokenValue::Object(out)
+}
+
+fn normalize_shadow_value(value: &serde_json::Value) -> TokenValue {
+  match value {
+    serde_json::Value::Array(entries) => {
+      let mut map = IndexMap::new();
+      for (i, entry) in entries.iter().enumerate() {
+        map.insert(i.to_string(), normalize_shadow_entry_value(entry));
+      }
+      TokenValue::Object(map)
+    }
+    serde_json::Value::Object(_) => normalize_shadow_entry_value(value),
+    other => json_leaf_to_tokenvalue(other),
+  }
+}
+
+fn normalize_typography_value(value: &serde_json::Value) -> TokenValue {
+  let serde_json::Value::Object(obj) = value else {
+    return json_leaf_to_tokenvalue(value);
+  };
+
+  let mut out = IndexMap::new();
+  for (k, v) in obj.iter() {
+    let normalized = match k.as_str() {
+      "fontSize" | "lineHeight" | "letterSpacing" => normalize_dimension_to_tokenvalue(v),
+      _ => json_leaf_to_tokenvalue(v),
+    };
+    out.insert(k.clone(), normalized);
+  }
+  TokenValue::Object(out)
+}
+
+fn normalize_border_value(value: &serde_json::Value) -> TokenValue {
+  let serde_json::Value::Object(obj) = value else {
+    return json_leaf_to_tokenvalue(value);
+  };
+
+  let mut out = IndexMap::new();
+  for (k, v) in obj.iter() {
+    let normalized = match k.as_str() {
+      "color" => v.as_str().map(|s| TokenValue::Color(normalize_color_string(s))).unwrap_or_else(|| json_leaf_to_tokenvalue(v)),
+      "width" => normalize_dimension_to_tokenvalue(v),
+      _ => json_leaf_to_tokenvalue(v),
+    };
+    out.insert(k.clone(), normalized);
+  }
+  TokenValue::Object(out)
+}
+
+fn normalize_gradient_value(value: &serde_json::Value) -> TokenValue {
+  let serde_json::Value::Array(stops) = value else {
+    return json_leaf_to_tokenvalue(value);
+  };
+
+  let mut map = IndexMap::new();
+  for (i, stop) in stops.iter().enumerate() {
+    let normalized = if let serde_json::Value::Object(obj) = stop {
+      let mut out = IndexMap::new();
+      for (k, v) in obj.iter() {
+        let nv = match k.as_str() {
+          "color" => v.as_str().map(|s| TokenValue::Color(normalize_color_string(s))).unwrap_or_else(|| json_leaf_to_tokenvalue(v)),
+          _ => json_leaf_to_tokenvalue(v),
+        };
+        out.insert(k.clone(), nv);
+      }
+      TokenValue::Object(out)
+    } else {
+      json_leaf_to_tokenvalue(stop)
+    };
+    map.insert(i.to_string(), normalized);
+  }
+  TokenValue::Object(map)
+}
+
+/// Convert a DTCG `$value` into the `TokenValue` stored for it, normalizing
+/// per `$type` along the way so downstream CSS emission sees spec-correct
+/// units rather than an echo of the raw JSON (canonical hex colors,
+/// durations in milliseconds, `fontWeight` keywords resolved to numbers,
+/// and composite types with their color/dimension fields normalized too).
+fn dtcg_value_to_tokenvalue(value: &serde_json::Value, resolved_type: Option<&str>) -> TokenValue {
+  if let serde_json::Value::String(s) = value {
+    if let Some(target) = dtcg_alias_target(s) {
+      return TokenValue::Alias(target);
+    }
+  }
+
+  match resolved_type {
+    Some("color") => match value.as_str() {
+      Some(s) => TokenValue::Color(normalize_color_string(s)),
+      None => json_leaf_to_tokenvalue(value),
+    },
+    Some("dimension") => normalize_dimension_to_tokenvalue(value),
+    Some("duration") => match value.as_str().and_then(normalize_duration_ms) {
+      Some(ms) => TokenValue::Dimension { value: ms, unit: "ms".to_string() },
+      None => json_leaf_to_tokenvalue(value),
+    },
+    Some("fontWeight") => match normalize_font_weight(value) {
+      Some(weight) => TokenValue::Number(weight),
+      None => json_leaf_to_tokenvalue(value),
+    },
+    Some("shadow") => normalize_shadow_value(value),
+    Some("typography") => normalize_typography_value(value),
+    Some("border") => normalize_border_value(value),
+    Some("gradient") => normalize_gradient_value(value),
+    _ => json_leaf_to_tokenvalue(value),
+  }
+}
+
+/// Recursively descend a DTCG JSON tree: any object carrying `$value` is a
+/// token leaf, everything else is a group. A token with no `$type` of its
+/// own inherits the nearest ancestor group's `$type` (DTCG type inheritance).
+fn flatten_dtcg_into_tokens(
+  prefix: &str,
+  value: &serde_json::Value,
+  inherited_type: Option<&str>,
+  out: &mut TokenSet,
+  errors: &mut Vec<TokenValidationError>,
+) {
+  let serde_json::Value::Object(obj) = value else {
+    return;
+  };
+
+  let own_type = obj.get("$type").and_then(|v| v.as_str());
+
+  if obj.contains_key("$value") {
+    let resolved_type = own_type.or(inherited_type);
+    let description = obj.get("$description").and_then(|v| v.as_str()).map(|s| s.to_string());
+    let raw_value = obj.get("$value").unwrap_or(&serde_json::Value::Null);
+    if let Some(token_type) = resolved_type {
+      validate_dtcg_value(token_type, raw_value, prefix, errors);
+    }
+    let token_value = dtcg_value_to_tokenvalue(raw_value, resolved_type);
+
+    out.insert(prefix.to_string(), Token { name: prefix.to_string(), value: token_value, comment: description });
+    return;
+  }
+
+  let group_type = own_type.or(inherited_type);
+  for (k, v) in obj.iter() {
+    if k.starts_with('$') {
+      continue;
+    }
+    let path = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
+    flatten_dtcg_into_tokens(&path, v, group_type, out, errors);
+  }
+}
+
+/// Parse a single DTCG-format JSON document into a flat, dotted-path
+/// `TokenSet` (e.g. `color.primary.background`). Complements (rather than
+/// replaces) `resolve_tokens`/`transformer::resolve_references`, since
+/// aliases on the resulting tokens still need resolving afterward.
+///
+/// `$type`-aware validation problems are not reported by this entry point -
+/// use `load_tokens_from_dtcg_json_validated` when they matter to the caller.
+pub fn load_tokens_from_dtcg_json(src: &str) -> Result<TokenSet, ResolveError> {
+  let value: serde_json::Value =
+    serde_json::from_str(src).map_err(|e| ResolveError::TransformFailed(format!("invalid DTCG JSON: {}", e)))?;
+
+  let mut tokens: TokenSet = IndexMap::new();
+  let mut errors = Vec::new();
+  flatten_dtcg_into_tokens("", &value, None, &mut tokens, &mut errors);
+  Ok(tokens)
+}
+
+/// Same as `load_tokens_from_dtcg_json`, but also returns every `$type`-aware
+/// validation problem found while flattening, each naming the offending
+/// token path, instead of discarding them.
+pub fn load_tokens_from_dtcg_json_validated(src: &str) -> Result<(TokenSet, Vec<TokenValidationError>), ResolveError> {
+  let value: serde_json::Value =
+    serde_json::from_str(src).map_err(|e| ResolveError::TransformFailed(format!("invalid DTCG JSON: {}", e)))?;
+
+  let mut tokens: TokenSet = IndexMap::new();
+  let mut errors = Vec::new();
+  flatten_dtcg_into_tokens("", &value, None, &mut tokens, &mut errors);
+  Ok((tokens, errors))
+}
+
+/// Merge token sets parsed from multiple DTCG files, erroring when two
+/// inputs define the same path with conflicting types rather than silently
+/// letting the later file win.
+pub fn merge_dtcg_token_sets(sets: Vec<TokenSet>) -> Result<TokenSet, ResolveError> {
+  let mut merged: TokenSet = IndexMap::new();
+  for set in sets {
+    for (path, token) in set {
+      if let Some(existing) = merged.get(&path) {
+        if std::mem::discriminant(&existing.value) != std::mem::discriminant(&token.value) {
+          return Err(ResolveError::TypeMismatch(format!(
+            "'{}' is defined with conflicting types across input files",
+            path
+          )));
+        }
+      }
+      merged.insert(path, token);
+    }
+  }
+  Ok(merged)
+}
+
+/// The DTCG leaf shape (`$type`/`$description`/`$value`) used to serialize a
+/// flat `TokenSet` back into nested JSON - the inverse of
+/// `load_tokens_from_dtcg_json`'s flattening.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TokenJson {
+  #[serde(rename = "$type", skip_serializing_if = "Option::is_none")]
+  token_type: Option<String>,
+  #[serde(rename = "$description", skip_serializing_if = "Option::is_none")]
+  description: Option<String>,
+  #[serde(rename = "$value")]
+  value: serde_json::Value,
+}
+
+fn tokenvalue_dtcg_type(value: &TokenValue) -> Option<&'static str> {
+  match value {
+    TokenValue::Color(_) => Some("color"),
+    TokenValue::Dimension { .. } => Some("dimension"),
+    _ => None,
+  }
+}
+
+fn tokenvalue_to_dtcg_json_value(value: &TokenValue) -> serde_json::Value {
+  match value {
+    TokenValue::Null => serde_json::Value::Null,
+    TokenValue::Bool(b) => serde_json::Value::Bool(*b),
+    TokenValue::Number(n) => serde_json::json!(n),
+    TokenValue::Color(s) => serde_json::Value::String(s.clone()),
+    TokenValue::Dimension { value, unit } => serde_json::Value::String(format!("{}{}", fmt_num(*value), unit)),
+    TokenValue::Alias(path) => serde_json::Value::String(format!("{{{}}}", path)),
+    TokenValue::Object(map) => {
+      let mut m = serde_json::Map::new();
+      for (k, v) in map.iter() {
+        m.insert(k.clone(), tokenvalue_to_dtcg_json_value(v));
+      }
+      serde_json::Value::Object(m)
+    }
+    other => serde_json::Value::String(token_value_to_string(other)),
+  }
+}
+
+fn insert_nested_dtcg(root: &mut serde_json::Map<String, serde_json::Value>, path: &str, leaf: serde_json::Value) {
+  let segments: Vec<&str> = path.split('.').collect();
+  let mut current = root;
+
+  for (i, seg) in segments.iter().enumerate() {
+    if i == segments.len() - 1 {
+      current.insert(seg.to_string(), leaf);
+      return;
+    }
+
+    let entry = current.entry(seg.to_string()).or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    if !entry.is_object() {
+      *entry = serde_json::Value::Object(serde_json::Map::new());
+    }
+    current = entry.as_object_mut().expect("just ensured this entry is an object");
+  }
+}
+
+/// Serialize a flat `TokenSet` back into nested DTCG-shaped JSON, the
+/// inverse of `load_tokens_from_dtcg_json`'s flattening.
+pub fn tokens_to_nested_dtcg_json(tokens: &TokenSet) -> serde_json::Value {
+  let mut root = serde_json::Map::new();
+
+  for (path, token) in tokens.iter() {
+    let leaf = TokenJson {
+      token_type: tokenvalue_dtcg_type(&token.value).map(|s| s.to_string()),
+      description: token.comment.clone(),
+      value: tokenvalue_to_dtcg_json_value(&token.value),
+    };
+    let leaf_json = serde_json::to_value(&leaf).unwrap_or(serde_json::Value::Null);
+    insert_nested_dtcg(&mut root, path, leaf_json);
+  }
+
+  serde_json::Value::Object(root)
+}
+
+// ---- DTCG $type-aware value validation ----
+
+#[derive(Debug, Error)]
+pub enum TokenValidationError {
+  #[error("{path}: {message}")]
+  Invalid { path: String, message: String },
+}
+
+fn validation_err(path: &str, message: impl Into<String>) -> TokenValidationError {
+  TokenValidationError::Invalid { path: path.to_string(), message: message.into() }
+}
+
+fn validate_color_value(value: &serde_json::Value, path: &str, errors: &mut Vec<TokenValidationError>) {
+  match value {
+    serde_json::Value::String(s) => {
+      let trimmed = s.trim();
+      let ok = looks_like_color(trimmed)
+        || trimmed.starts_with("hsl(")
+        || trimmed.starts_with("hsla(")
+        || dtcg_alias_target(trimmed).is_some();
+      if !ok {
+        errors.push(validation_err(path, format!("'{}' is not a recognized color literal", s)));
+      }
+    }
+    other => errors.push(validation_err(path, format!("color tokens need a string $value, got {}", other))),
+  }
+}
+
+fn validate_dimension_value(value: &serde_json::Value, path: &str, errors: &mut Vec<TokenValidationError>) {
+  let ok = match value {
+    serde_json::Value::String(s) => dtcg_alias_target(s).is_some() || parse_dimension_string(s).is_some(),
+    serde_json::Value::Object(obj) => {
+      obj.get("value").and_then(|v| v.as_f64()).is_some() && obj.get("unit").and_then(|v| v.as_str()).is_some()
+    }
+    _ => false,
+  };
+  if !ok {
+    errors.push(validation_err(path, "dimension tokens need a numeric value with a unit (e.g. '4px') or { value, unit }"));
+  }
+}
+
+fn validate_duration_value(value: &serde_json::Value, path: &str, errors: &mut Vec<TokenValidationError>) {
+  match value {
+    serde_json::Value::String(s) if dtcg_alias_target(s).is_some() => {}
+    serde_json::Value::String(s) => {
+      let trimmed = s.trim();
+      let ok = trimmed
+        .strip_suffix("ms")
+        .or_else(|| trimmed.strip_suffix('s'))
+        .is_some_and(|n| n.parse::<f64>().is_ok());
+      if !ok {
+        errors.push(validation_err(path, format!("'{}' is not a duration (expected e.g. '200ms' or '1.5s')", s)));
+      }
+    }
+    other => errors.push(validation_err(path, format!("duration tokens need a string $value like '200ms', got {}", other))),
+  }
+}
+
+fn validate_font_weight_value(value: &serde_json::Value, path: &str, errors: &mut Vec<TokenValidationError>) {
+  const KEYWORDS: [&str; 4] = ["normal", "bold", "bolder", "lighter"];
+  match value {
+    serde_json::Value::Number(n) => {
+      let n = n.as_f64().unwrap_or(0.0);
+      if !(1.0..=1000.0).contains(&n) {
+        errors.push(validation_err(path, format!("font weight {} is outside the valid 1-1000 range", n)));
+      }
+    }
+    serde_json::Value::String(s) if dtcg_alias_target(s).is_some() => {}
+    serde_json::Value::String(s) if KEYWORDS.contains(&s.to_lowercase().as_str()) => {}
+    other => errors.push(validation_err(path, format!("'{}' is not a valid font weight", other))),
+  }
+}
+
+fn validate_object_field<'a>(
+  obj: &'a serde_json::Map<String, serde_json::Value>,
+  field: &str,
+  path: &str,
+  errors: &mut Vec<TokenValidationError>,
+) -> Option<&'a serde_json::Value> {
+  match obj.get(field) {
+    Some(v) => Some(v),
+    None => {
+      errors.push(validation_err(path, format!("missing required field '{}'", field)));
+      None
+    }
+  }
+}
+
+fn validate_shadow_entry(value: &serde_json::Value, path: &str, errors: &mut Vec<TokenValidationError>) {
+  let serde_json::Value::Object(obj) = value else {
+    errors.push(validation_err(path, "each shadow must be an object with offsetX/offsetY/blur/color"));
+    return;
+  };
+
+  for field in ["offsetX", "offsetY", "blur"] {
+    if let Some(v) = validate_object_field(obj, field, path, errors) {
+      validate_dimension_value(v, path, errors);
+    }
+  }
+  if let Some(color) = validate_object_field(obj, "color", path, errors) {
+    validate_color_value(color, path, errors);
+  }
+}
+
+fn validate_shadow_value(value: &serde_json::Value, path: &str, errors: &mut Vec<TokenValidationError>) {
+  match value {
+    serde_json::Value::Array(entries) => {
+      for entry in entries {
+        validate_shadow_entry(entry, path, errors);
+      }
+    }
+    serde_json::Value::Object(_) => validate_shadow_entry(value, path, errors),
+    _ => errors.push(validation_err(path, "shadow tokens must be an object or array of objects")),
+  }
+}
+
+fn validate_typography_value(value: &serde_json::Value, path: &str, errors: &mut Vec<TokenValidationError>) {
+  let serde_json::Value::Object(obj) = value else {
+    errors.push(validation_err(path, "typography tokens must be a composite object"));
+    return;
+  };
+
+  if let Some(family) = validate_object_field(obj, "fontFamily", path, errors) {
+    if !family.is_string() {
+      errors.push(validation_err(path, "'fontFamily' must be a string"));
+    }
+  }
+  if let Some(size) = validate_object_field(obj, "fontSize", path, errors) {
+    validate_dimension_value(size, path, errors);
+  }
+}
+
+fn validate_border_value(value: &serde_json::Value, path: &str, errors: &mut Vec<TokenValidationError>) {
+  const STYLES: [&str; 9] = ["solid", "dashed", "dotted", "double", "groove", "ridge", "inset", "outset", "none"];
+  let serde_json::Value::Object(obj) = value else {
+    errors.push(validation_err(path, "border tokens must be a composite object"));
+    return;
+  };
+
+  if let Some(color) = validate_object_field(obj, "color", path, errors) {
+    validate_color_value(color, path, errors);
+  }
+  if let Some(width) = validate_object_field(obj, "width", path, errors) {
+    validate_dimension_value(width, path, errors);
+  }
+  if let Some(style) = validate_object_field(obj, "style", path, errors) {
+    match style.as_str() {
+      Some(s) if STYLES.contains(&s.to_lowercase().as_str()) => {}
+      _ => errors.push(validation_err(path, format!("'{}' is not a recognized border style", style))),
+    }
+  }
+}
+
+fn validate_gradient_value(value: &serde_json::Value, path: &str, errors: &mut Vec<TokenValidationError>) {
+  let serde_json::Value::Array(stops) = value else {
+    errors.push(validation_err(path, "gradient tokens must be an array of color stops"));
+    return;
+  };
+
+  if stops.is_empty() {
+    errors.push(validation_err(path, "gradient tokens need at least one color stop"));
+  }
+
+  for stop in stops {
+    let serde_json::Value::Object(obj) = stop else {
+      errors.push(validation_err(path, "each gradient stop must be an object with color/position"));
+      continue;
+    };
+    if let Some(color) = validate_object_field(obj, "color", path, errors) {
+      validate_color_value(color, path, errors);
+    }
+    if let Some(position) = validate_object_field(obj, "position", path, errors) {
+      if !position.is_number() {
+        errors.push(validation_err(path, "'position' must be a number between 0 and 1"));
+      }
+    }
+  }
+}
+
+/// Validate a DTCG leaf's `$value` against the shape its `$type` implies.
+/// Unrecognized types are left unvalidated rather than rejected, since DTCG
+/// allows vendor-specific `$type`s this crate doesn't otherwise model.
+fn validate_dtcg_value(token_type: &str, value: &serde_json::Value, path: &str, errors: &mut Vec<TokenValidationError>) {
+  match token_type {
+    "color" => validate_color_value(value, path, errors),
+    "dimension" => validate_dimension_value(value, path, errors),
+    "duration" => validate_duration_value(value, path, errors),
+    "fontWeight" => validate_font_weight_value(value, path, errors),
+    "shadow" => validate_shadow_value(value, path, errors),
+    "typography" => validate_typography_value(value, path, errors),
+    "border" => validate_border_value(value, path, errors),
+    "gradient" => validate_gradient_value(value, path, errors),
+    _ => {}
+  }
+}
+
+/// Flatten a nested JSON object tree into a `TokenSet` keyed by dotted path
+/// (e.g. `spacing.base`), treating any object matching the dimension/color
+/// conventions above as a token leaf rather than a further-nested group.
+#[cfg(feature = "jsonnet")]
+fn flatten_json_into_tokens(prefix: &str, val: &serde_json::Value, out: &mut TokenSet) {
+  match val {
+    serde_json::Value::Object(obj) if !is_token_leaf(obj) => {
+      for (k, v) in obj.iter() {
+        let path = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
+        flatten_json_into_tokens(&path, v, out);
+      }
+    }
+    other => {
+      out.insert(
+        prefix.to_string(),
+        Token { name: prefix.to_string(), value: json_leaf_to_tokenvalue(other), comment: None },
+      );
+    }
+  }
+}
+
+#[cfg(feature = "jsonnet")]
+fn is_token_leaf(obj: &serde_json::Map<String, serde_json::Value>) -> bool {
+  matches!(obj.get("type").and_then(|t| t.as_str()), Some("dimension"))
+}
+
+/// Evaluate a `.jsonnet` source string and flatten its JSON output into a
+/// `TokenSet`. Complements (rather than replaces) `resolve_tokens`, since
+/// aliases/references/transforms on the resulting tokens still run afterward.
+#[cfg(feature = "jsonnet")]
+pub fn load_tokens_from_jsonnet(src: &str) -> Result<TokenSet, ResolveError> {
+  let mut vm = jsonnet::JsonnetVm::new();
+  let json_str = vm
+    .evaluate_snippet("tokens.jsonnet", src)
+    .map_err(|e| ResolveError::TransformFailed(format!("Jsonnet evaluation error: {}", e)))?;
+
+  let value: serde_json::Value = serde_json::from_str(&json_str)
+    .map_err(|e| ResolveError::TransformFailed(format!("Jsonnet output was not valid JSON: {}", e)))?;
+
+  let mut tokens: TokenSet = IndexMap::new();
+  flatten_json_into_tokens("", &value, &mut tokens);
+  Ok(tokens)
+}
+
+pub fn to_css_custom_properties(tokens: &TokenSet) -> IndexMap<String, String> {
+  let mut map = IndexMap::new();
+  for (key, token) in tokens.iter() {
+    let css_name = format!("--{}", key.replace('.', "-"));
+    let css_value = token_value_to_string(&token.value);
+    map.insert(css_name, css_value);
+  }
+  map
+}
+
+/// True if every key in `obj` is its own stringified position (`"0"`, `"1"`,
+/// ...) - the shape `normalize_shadow_value`/`normalize_gradient_value` give
+/// a multi-entry shadow list or a gradient's stop list.
+fn is_index_keyed_object(obj: &IndexMap<String, TokenValue>) -> bool {
+  !obj.is_empty() && obj.keys().enumerate().all(|(i, k)| k == &i.to_string())
+}
+
+/// Render one `shadow` entry (as produced by `normalize_shadow_entry_value`)
+/// as a `box-shadow`/`text-shadow`-compatible value, e.g. `1px 2px 3px #000`.
+fn render_shadow_entry(obj: &IndexMap<String, TokenValue>) -> String {
+  let mut parts = Vec::new();
+  for field in ["offsetX", "offsetY", "blur", "spread"] {
+    if let Some(v) = obj.get(field) {
+      parts.push(token_value_to_string(v));
+    }
+  }
+  if let Some(color) = obj.get("color") {
+    parts.push(token_value_to_string(color));
+  }
+  parts.join(" ")
+}
+
+/// Render a `border` object (as produced by `normalize_border_value`) as a
+/// `border`-shorthand-compatible value, e.g. `1px solid #000`.
+fn render_border_value(obj: &IndexMap<String, TokenValue>) -> String {
+  ["width", "style", "color"]
+    .into_iter()
+    .filter_map(|field| obj.get(field).map(token_value_to_string))
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// Best-effort `font`-shorthand rendering of a `typography` object (as
+/// produced by `normalize_typography_value`): `weight size/lineHeight family`.
+/// `letterSpacing` has no place in the `font` shorthand, so callers that need
+/// it should read the field directly rather than relying on this string.
+fn render_typography_value(obj: &IndexMap<String, TokenValue>) -> String {
+  let mut parts = Vec::new();
+  if let Some(weight) = obj.get("fontWeight") {
+    parts.push(token_value_to_string(weight));
+  }
+  match (obj.get("fontSize"), obj.get("lineHeight")) {
+    (Some(size), Some(line)) => parts.push(format!("{}/{}", token_value_to_string(size), token_value_to_string(line))),
+    (Some(size), None) => parts.push(token_value_to_string(size)),
+    _ => {}
+  }
+  if let Some(family) = obj.get("fontFamily") {
+    parts.push(token_value_to_string(family));
+  }
+  parts.join(" ")
+}
+
+/// Render a composite `shadow`/`border`/`typography`/`gradient`
+/// `TokenValue::Object` (as produced by the `normalize_*_value` helpers) as
+/// real CSS syntax instead of an opaque JSON blob, inferring which shape it
+/// is from its fields since `TokenValue::Object` itself doesn't carry the
+/// originating DTCG `$type`. Anything that doesn't match a known shape falls
+/// back to a JSON dump so unrecognized/future object shapes are still visible.
+fn render_composite_object(obj: &IndexMap<String, TokenValue>) -> String {
+  if is_index_keyed_object(obj) {
+    if let Some(TokenValue::Object(first)) = obj.get("0") {
+      if first.contains_key("offsetX") || first.contains_key("offsetY") {
+        return obj
+          .values()
+          .map(|v| match v {
+            TokenValue::Object(entry) => render_shadow_entry(entry),
+            other => token_value_to_string(other),
+          })
+          .collect::<Vec<_>>()
+          .join(", ");
+      }
+      if first.contains_key("color") {
+        return format!(
+          "linear-gradient({})",
+          obj
+            .values()
+            .map(|v| match v {
+              TokenValue::Object(stop) => {
+                let color = stop.get("color").map(token_value_to_string).unwrap_or_default();
+                match stop.get("position") {
+                  Some(position) => format!("{} {}", color, token_value_to_string(position)),
+                  None => color,
+                }
+              }
+              other => token_value_to_string(other),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+        );
+      }
+    }
+  } else if obj.contains_key("offsetX") || obj.contains_key("offsetY") {
+    return render_shadow_entry(obj);
+  } else if obj.contains_key("width") && (obj.contains_key("style") || obj.contains_key("color")) {
+    return render_border_value(obj);
+  } else if obj.contains_key("fontSize") || obj.contains_key("fontFamily") {
+    return render_typography_value(obj);
+  }
+
+  serde_json::to_string(obj).unwrap_or_else(|_| String::from("{}"))
+}
+
+pub fn token_value_to_string(value: &TokenValue) -> String {
+  match value {
+    TokenValue::String(s) => s.clone(),
+    TokenValue::Number(n) => {
+      if (n.fract()).abs() < std::f64::EPSILON {
+        format!("{:.0}", n)
+      } else {
+        n.to_string()
+      }
+    }
+    TokenValue::Bool(b) => b.to_string(),
+    TokenValue::Object(obj) => render_composite_object(obj),
+    TokenValue::Alias(a) => format!("alias({})", a),
+    // Callers that care about per-target CSS key style (`emit_css`) match
+    // `Reference` themselves before reaching this function; everyone else
+    // (e.g. `to_resolved_string_map`, `to_css_custom_properties`) gets this
+    // default dash-separated `var(...)` rendering instead.
+    TokenValue::Reference(r) => reference_to_calc_var(r),
+    TokenValue::Color(c) => c.clone(),
+    TokenValue::Dimension { value, unit } => {
+      if (value.fract()).abs() < std::f64::EPSILON {
+        format!("{:.0}{}", value, unit)
+      } else {
+        format!("{}{}", value, unit)
+      }
+    }
+    TokenValue::Transform(_) => "unresolved-transform".to_string(),
+    TokenValue::Null => String::from("null"),
+  }
+}
+
+#[cfg(test)]
+mod composite_css_tests {
+  use super::*;
+
+  #[test]
+  fn shadow_entry_renders_as_box_shadow_syntax() {
+    let shadow = normalize_shadow_value(&serde_json::json!({
+      "offsetX": "1px", "offsetY": "2px", "blur": "3px", "spread": "0px", "color": "#000000"
+    }));
+    let css = token_value_to_string(&shadow);
+    assert_eq!(css, "1px 2px 3px 0px #000000");
+    assert!(serde_json::from_str::<serde_json::Value>(&css).is_err(), "expected CSS syntax, not JSON");
+  }
+
+  #[test]
+  fn shadow_list_renders_as_comma_separated_box_shadow_syntax() {
+    let shadow = normalize_shadow_value(&serde_json::json!([
+      {"offsetX": "1px", "offsetY": "1px", "blur": "2px", "color": "#000000"},
+      {"offsetX": "0px", "offsetY": "0px", "blur": "0px", "color": "#ffffff"}
+    ]));
+    let css = token_value_to_string(&shadow);
+    assert_eq!(css, "1px 1px 2px #000000, 0px 0px 0px #ffffff");
+  }
+
+  #[test]
+  fn border_renders_as_border_shorthand_syntax() {
+    let border = normalize_border_value(&serde_json::json!({
+      "width": "1px", "style": "solid", "color": "#000000"
+    }));
+    let css = token_value_to_string(&border);
+    assert_eq!(css, "1px solid #000000");
+    assert!(serde_json::from_str::<serde_json::Value>(&css).is_err(), "expected CSS syntax, not JSON");
+  }
+}
+
+// ---- Configurable CSS custom-property key generation ----
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CssKeyOptions {
+  pub prefix: Option<String>,
+  pub separator: char,
+  pub lowercase: bool,
+}
+
+impl Default for CssKeyOptions {
+  fn default() -> Self {
+    Self {
+      prefix: None,
+      separator: '-',
+      lowercase: true,
+    }
+  }
+}
+
+fn normalize_token(s: &str, separator: char, lowercase: bool) -> String {
+  let mut out = String::new();
+  let mut prev_was_sep = false;
+  let mut prev_was_lower_or_digit = false;
+
+  for ch in s.chars() {
+    if ch.is_ascii_alphanumeric() {
+      if ch.is_ascii_uppercase() {
+        if prev_was_lower_or_digit && !prev_was_sep && !out.ends_with(separator) {
+          out.push(separator);
+        }
+        out.push(if lowercase { ch.to_ascii_lowercase() } else { ch });
+        prev_was_sep = false;
+        prev_was_lower_or_digit = true;
+      } else {
+        out.push(if lowercase { ch.to_ascii_lowercase() } else { ch });
+        prev_was_sep = false;
+        prev_was_lower_or_digit = true;
+      }
+    } else {
+      if !out.ends_with(separator) {
+        out.push(separator);
+      }
+      prev_was_sep = true;
+      prev_was_lower_or_digit = false;
+    }
+  }
+
+  out.trim_matches(separator).to_string()
+}
+
+fn normalize_path(s: &str, separator: char, lowercase: bool) -> String {
+  let mut parts = Vec::new();
+  let mut current = String::new();
+
+  for ch in s.chars() {
+    if ch == '.' || ch == '/' || ch.is_whitespace() {
+      if !current.is_empty() {
+        parts.push(current.clone());
+        current.clear();
+      }
+    } else {
+      current.push(ch);
+    }
+  }
+  if !current.is_empty() {
+    parts.push(current);
+  }
+
+  let mut out = String::new();
+  for token in parts.iter().map(|t| normalize_token(t, separator, lowercase)) {
+    if token.is_empty() {
+      continue;
+    }
+    if !out.is_empty() {
+      out.push(separator);
+    }
+    out.push_str(&token);
+  }
+  out
+}
+
+/// Turn a dotted/slashed token path (e.g. `color.primary.background`,
+/// `borderRadius.sm`) into a normalized CSS custom-property name
+/// (`--color-primary-background`, `--border-radius-sm`), honoring an
+/// optional namespace prefix, separator, and casing from `opts`.
+pub fn make_css_custom_property_key(path: &str, opts: &CssKeyOptions) -> String {
+  let mut out = String::new();
+
+  if let Some(prefix) = &opts.prefix {
+    let norm_prefix = normalize_token(prefix, opts.separator, opts.lowercase);
+    if !norm_prefix.is_empty() {
+      out.push_str(&norm_prefix);
+    }
+  }
+
+  let body = path.trim_start().strip_prefix("--").unwrap_or(path.trim());
+  let norm_body = normalize_path(body, opts.separator, opts.lowercase);
+
+  if !norm_body.is_empty() {
+    if !out.is_empty() {
+      out.push(opts.separator);
+    }
+    out.push_str(&norm_body);
+  }
+
+  let mut final_key = String::with_capacity(out.len() + 2);
+  final_key.push_str("--");
+  final_key.push_str(&out);
+  final_key
+}
+
+/// `var(--the-normalized-key)`, for referencing a token from elsewhere.
+pub fn css_var(path: &str, opts: &CssKeyOptions) -> String {
+  format!("var({})", make_css_custom_property_key(path, opts))
+}
+
+/// Emit a `:root`-style block of CSS custom properties from a resolved
+/// `TokenSet`, using `make_css_custom_property_key` for the property names
+/// instead of the simpler fixed `--dot-to-dash` replacement in
+/// `to_css_stylesheet`.
+pub fn emit_css(tokens: &TokenSet, selector: &str, opts: &CssKeyOptions) -> String {
+  let mut out = String::new();
+  out.push_str(selector);
+  out.push_str(" {\n");
+
+  for (key, token) in tokens.iter() {
+    let var_name = make_css_custom_property_key(key, opts);
+    let value = match &token.value {
+      // An unresolved reference/alias still points at another token path -
+      // emit a `var(--...)` lookup built with `opts` instead of
+      // `token_value_to_string`'s `alias(...)` placeholder or its fixed
+      // dash-separated `var(...)` for a reference.
+      TokenValue::Reference(target) | TokenValue::Alias(target) => css_var(target, opts),
+      other => token_value_to_string(other),
+    };
+    out.push_str("  ");
+    out.push_str(&var_name);
+    out.push_str(": ");
+    out.push_str(&value);
+    out.push_str(";\n");
+  }
+
+  out.push('}');
+  out
+}
+
+/// Emit one `emit_css` block per named variant (e.g. a theme's light/dark
+/// pair), each scoped under its own `{base_selector}[data-theme="name"]`
+/// selector but sharing the same `opts` - so the same property names
+/// appear in every block, and only the values differ between variants.
+pub fn emit_themed_css(base_selector: &str, variants: &[(String, TokenSet)], opts: &CssKeyOptions) -> String {
+  let mut out = String::new();
+
+  for (variant_name, tokens) in variants {
+    if !out.is_empty() {
+      out.push('\n');
+    }
+    let selector = format!("{}[data-theme=\"{}\"]", base_selector, variant_name);
+    out.push_str(&emit_css(tokens, &selector, opts));
+    out.push('\n');
+  }
+
+  out
+}
+
+#[cfg(test)]
+mod css_key_tests {
+  use super::*;
+
+  #[test]
+  fn default_simple_dot_separated() {
+    let opts = CssKeyOptions::default();
+    assert_eq!(make_css_custom_property_key("color.primary.background", &opts), "--color-primary-background");
+  }
+
+  #[test]
+  fn default_slash_and_spaces() {
+    let opts = CssKeyOptions::default();
+    assert_eq!(make_css_custom_property_key("color/theme / primary 500", &opts), "--color-theme-primary-500");
+  }
+
+  #[test]
+  fn preserves_leading_dashes_once() {
+    let opts = CssKeyOptions::default();
+    assert_eq!(make_css_custom_property_key("--color.primary-500", &opts), "--color-primary-500");
+  }
+
+  #[test]
+  fn camel_and_pascal_case_boundaries() {
+    let opts = CssKeyOptions::default();
+    assert_eq!(make_css_custom_property_key("Color.PrimaryAccent", &opts), "--color-primary-accent");
+    assert_eq!(make_css_custom_property_key("borderRadius.sm", &opts), "--border-radius-sm");
+  }
+
+  #[test]
+  fn custom_separator_underscore() {
+    let opts = CssKeyOptions { prefix: None, separator: '_', lowercase: true };
+    assert_eq!(make_css_custom_property_key("color.primary.500", &opts), "--color_primary_500");
+  }
+
+  #[test]
+  fn with_prefix_namespace() {
+    let opts = CssKeyOptions { prefix: Some("dark".to_string()), ..Default::default() };
+    assert_eq!(make_css_custom_property_key("color.primary.500", &opts), "--dark-color-primary-500");
+  }
+
+  #[test]
+  fn complex_input_sanitization_and_collapse() {
+    let opts = CssKeyOptions::default();
+    assert_eq!(make_css_custom_property_key("layout..grid   cols", &opts), "--layout-grid-cols");
+    assert_eq!(make_css_custom_property_key("color---primary", &opts), "--color-primary");
+    assert_eq!(make_css_custom_property_key("size(2x)@md", &opts), "--size-2x-md");
+  }
+
+  #[test]
+  fn css_var_wrapper() {
+    let opts = CssKeyOptions { prefix: Some("theme".to_string()), ..Default::default() };
+    assert_eq!(css_var("Color.Primary.500", &opts), "var(--theme-color-primary-500)");
+  }
+
+  #[test]
+  fn css_var_default_reference_style() {
+    let opts = CssKeyOptions::default();
+    assert_eq!(css_var("a.b.c", &opts), "var(--a-b-c)");
+    assert_eq!(css_var("button/Primary.sizeLg", &opts), "var(--button-primary-size-lg)");
+  }
+
+  #[test]
+  fn css_var_with_prefix_reference_style() {
+    let opts = CssKeyOptions { prefix: Some("app".into()), ..Default::default() };
+    assert_eq!(css_var("a.b.c", &opts), "var(--app-a-b-c)");
+    assert_eq!(css_var("Color.Primary", &opts), "var(--app-color-primary)");
+  }
+
+  #[test]
+  fn empty_like_inputs_do_not_break() {
+    let opts = CssKeyOptions::default();
+    assert_eq!(make_css_custom_property_key("", &opts), "--");
+    assert_eq!(make_css_custom_property_key("--", &opts), "--");
+    assert_eq!(make_css_custom_property_key("   ", &opts), "--");
+  }
+
+  #[test]
+  fn prefix_is_normalized() {
+    let opts = CssKeyOptions { prefix: Some("Dark Mode".into()), ..Default::default() };
+    assert_eq!(make_css_custom_property_key("Color.Primary", &opts), "--dark-mode-color-primary");
+  }
+
+  /// `resolve_tokens` must leave a `Reference` unbaked so `emit_css` can
+  /// render it with the *target*'s own `CssKeyOptions` - if a reference were
+  /// baked into a hardcoded dash-separated `var(...)` string up front, this
+  /// would drift from the real property name as soon as a target configured
+  /// a non-default prefix/separator.
+  #[test]
+  fn reference_is_rendered_with_target_css_key_options_after_resolve() {
+    let mut tokens: TokenSet = IndexMap::new();
+    tokens.insert(
+      "color.brand.base".to_string(),
+      Token { name: "color.brand.base".to_string(), value: TokenValue::Color("#336699".to_string()), comment: None },
+    );
+    tokens.insert(
+      "color.brand.accent".to_string(),
+      Token { name: "color.brand.accent".to_string(), value: TokenValue::Reference("color.brand.base".to_string()), comment: None },
+    );
+
+    let resolved = resolve_tokens(&tokens).expect("resolve_tokens should succeed");
+    let opts = CssKeyOptions { prefix: Some("app".into()), separator: '_', lowercase: true };
+    let css = emit_css(&resolved, ":root", &opts);
+
+    let expected_target_key = make_css_custom_property_key("color.brand.base", &opts);
+    assert!(
+      css.contains(&format!("var({})", expected_target_key)),
+      "expected emitted CSS to reference the target's own key {:?}, got:\n{}",
+      expected_target_key,
+      css
+    );
+  }
+}
+
+/// Build a CSS stylesheet with a selector (e.g., ":root") and optional prefix for variable names.
+pub fn to_css_stylesheet(tokens: &TokenSet, selector: &str, prefix: Option<&str>) -> String {
+  let mut out = String::new();
+  out.push_str(selector);
+  out.push_str(" {\n");
+  for (key, token) in tokens.iter() {
+    let var_name = match prefix {
+      Some(p) if !p.is_empty() => format!("--{}-{}", p, key.replace('.', "-")),
+      _ => format!("--{}", key.replace('.', "-")),
+    };
+    let value = token_value_to_string(&token.value);
+    out.push_str("  ");
+    out.push_str(&var_name);
+    out.push_str(": ");
+    out.push_str(&value);
+    out.push_str(";\n");
+  }
+  out.push('}');
+  out
+}
+
+/// Produce a mapping from token path (e.g., "spacing.base") to resolved string value.
+pub fn to_resolved_string_map(tokens: &TokenSet) -> IndexMap<String, String> {
+  let mut map = IndexMap::new();
+  for (key, token) in tokens.iter() {
+    map.insert(key.clone(), token_value_to_string(&token.value));
+  }
+  map
+}
+
+pub fn merge_token_sets(base: &TokenSet, overrides: &TokenSet) -> TokenSet {
+  let mut out = base.clone();
   for (k, v) in overrides.iter() {
     out.insert(k.clone(), v.clone());
   }
   out
 }
 
+// ---- Inline expression parsing (`{a} * 4 + 2px`) into a TransformExpr ----
+
+#[derive(Debug, Clone)]
+enum ExprToken {
+  Ident(String),
+  Number(f64),
+  Unit(f64, String),
+  Plus,
+  Minus,
+  Star,
+  Slash,
+  LParen,
+  RParen,
+}
+
+fn lex_transform_expr(input: &str) -> Result<Vec<ExprToken>, ResolveError> {
+  let chars: Vec<char> = input.chars().collect();
+  let mut tokens = Vec::new();
+  let mut i = 0;
+
+  while i < chars.len() {
+    let c = chars[i];
+    if c.is_whitespace() {
+      i += 1;
+      continue;
+    }
+
+    match c {
+      '{' => {
+        let start = i + 1;
+        let mut j = start;
+        while j < chars.len() && chars[j] != '}' {
+          j += 1;
+        }
+        if j >= chars.len() {
+          return Err(ResolveError::InvalidTransform(format!("unterminated '{{' in expression: '{}'", input)));
+        }
+        let ident: String = chars[start..j].iter().collect();
+        tokens.push(ExprToken::Ident(ident.trim().to_string()));
+        i = j + 1;
+      }
+      '+' => { tokens.push(ExprToken::Plus); i += 1; }
+      '-' => { tokens.push(ExprToken::Minus); i += 1; }
+      '*' => { tokens.push(ExprToken::Star); i += 1; }
+      '/' => { tokens.push(ExprToken::Slash); i += 1; }
+      '(' => { tokens.push(ExprToken::LParen); i += 1; }
+      ')' => { tokens.push(ExprToken::RParen); i += 1; }
+      c if c.is_ascii_digit() || c == '.' => {
+        let start = i;
+        let mut j = i;
+        while j < chars.len() && (chars[j].is_ascii_digit() || chars[j] == '.') {
+          j += 1;
+        }
+        let num_str: String = chars[start..j].iter().collect();
+        let value: f64 = num_str
+          .parse()
+          .map_err(|_| ResolveError::InvalidTransform(format!("invalid number '{}' in expression: '{}'", num_str, input)))?;
+
+        let unit_start = j;
+        let mut k = j;
+        while k < chars.len() && (chars[k].is_ascii_alphabetic() || chars[k] == '%') {
+          k += 1;
+        }
+        if k > unit_start {
+          let unit: String = chars[unit_start..k].iter().collect();
+          tokens.push(ExprToken::Unit(value, unit));
+        } else {
+          tokens.push(ExprToken::Number(value));
+        }
+        i = k;
+      }
+      other => return Err(ResolveError::InvalidTransform(format!("unexpected character '{}' in expression: '{}'", other, input))),
+    }
+  }
+
+  Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+enum ExprNode {
+  Leaf(TokenValue),
+  BinOp(Box<ExprNode>, char, Box<ExprNode>),
+}
+
+struct ExprParser<'a> {
+  tokens: &'a [ExprToken],
+  pos: usize,
+}
+
+impl<'a> ExprParser<'a> {
+  fn peek(&self) -> Option<&ExprToken> {
+    self.tokens.get(self.pos)
+  }
+
+  // expr := term (('+' | '-') term)*
+  fn parse_expr(&mut self) -> Result<ExprNode, ResolveError> {
+    let mut lhs = self.parse_term()?;
+    loop {
+      let op = match self.peek() {
+        Some(ExprToken::Plus) => '+',
+        Some(ExprToken::Minus) => '-',
+        _ => break,
+      };
+      self.pos += 1;
+      let rhs = self.parse_term()?;
+      lhs = ExprNode::BinOp(Box::new(lhs), op, Box::new(rhs));
+    }
+    Ok(lhs)
+  }
+
+  // term := factor (('*' | '/') factor)*
+  fn parse_term(&mut self) -> Result<ExprNode, ResolveError> {
+    let mut lhs = self.parse_factor()?;
+    loop {
+      let op = match self.peek() {
+        Some(ExprToken::Star) => '*',
+        Some(ExprToken::Slash) => '/',
+        _ => break,
+      };
+      self.pos += 1;
+      let rhs = self.parse_factor()?;
+      lhs = ExprNode::BinOp(Box::new(lhs), op, Box::new(rhs));
+    }
+    Ok(lhs)
+  }
+
+  // factor := '{' ident '}' | number | number unit | '(' expr ')'
+  fn parse_factor(&mut self) -> Result<ExprNode, ResolveError> {
+    let tok = self.tokens.get(self.pos).cloned();
+    self.pos += 1;
+    match tok {
+      Some(ExprToken::Ident(path)) => Ok(ExprNode::Leaf(TokenValue::Alias(path))),
+      Some(ExprToken::Number(n)) => Ok(ExprNode::Leaf(TokenValue::Number(n))),
+      Some(ExprToken::Unit(value, unit)) => Ok(ExprNode::Leaf(TokenValue::Dimension { value, unit })),
+      Some(ExprToken::LParen) => {
+        let inner = self.parse_expr()?;
+        match self.tokens.get(self.pos) {
+          Some(ExprToken::RParen) => {
+            self.pos += 1;
+            Ok(inner)
+          }
+          _ => Err(ResolveError::InvalidTransform("expected closing ')' in expression".into())),
+        }
+      }
+      Some(other) => Err(ResolveError::InvalidTransform(format!("unexpected token '{:?}' where a value was expected", other))),
+      None => Err(ResolveError::InvalidTransform("unexpected end of expression".into())),
+    }
+  }
+}
+
+/// An operand that isn't a plain literal (an alias, or a nested binary op)
+/// becomes a grouped sub-pipeline rather than being inlined directly as a
+/// step arg, since only `Number`/`Dimension` are valid builtin op args -
+/// `resolve_step_args` evaluates it at resolve time before the op runs.
+fn expr_node_to_arg(node: &ExprNode) -> TokenValue {
+  match node {
+    ExprNode::Leaf(TokenValue::Number(n)) => TokenValue::Number(*n),
+    ExprNode::Leaf(TokenValue::Dimension { value, unit }) => TokenValue::Dimension { value: *value, unit: unit.clone() },
+    other => TokenValue::Transform(flatten_expr_node(other)),
+  }
+}
+
+fn flatten_expr_node(node: &ExprNode) -> TransformExpr {
+  match node {
+    ExprNode::Leaf(TokenValue::Alias(path)) => TransformExpr {
+      steps: vec![TransformStep { r#type: "alias".into(), args: vec![TokenValue::String(path.clone())] }],
+    },
+    ExprNode::Leaf(v) => TransformExpr {
+      steps: vec![TransformStep { r#type: "literal".into(), args: vec![v.clone()] }],
+    },
+    ExprNode::BinOp(lhs, op, rhs) => {
+      let mut expr = flatten_expr_node(lhs);
+      let op_type = match op {
+        '+' => "add",
+        '-' => "subtract",
+        '*' => "multiply",
+        '/' => "divide",
+        _ => unreachable!("lexer only ever produces +, -, *, /"),
+      };
+      expr.steps.push(TransformStep { r#type: op_type.into(), args: vec![expr_node_to_arg(rhs)] });
+      expr
+    }
+  }
+}
+
+/// Parse an inline arithmetic expression, e.g. `{spacing.base} * 4 + 2px` or
+/// `({base.ref} + 1rem) / 2`, into the equivalent `TransformExpr` pipeline -
+/// so token authors can write ordinary infix math instead of spelling out
+/// `TransformStep`s by hand. `*`/`/` bind tighter than `+`/`-`, both groups
+/// are left-associative, and parens group as usual; unit mismatches inside
+/// `add`/`subtract` are still caught by those builtins at resolve time.
+pub fn parse_transform_expr(input: &str) -> Result<TransformExpr, ResolveError> {
+  let tokens = lex_transform_expr(input)?;
+  if tokens.is_empty() {
+    return Err(ResolveError::InvalidTransform(format!("empty expression: '{}'", input)));
+  }
+  if matches!(tokens[0], ExprToken::Plus | ExprToken::Minus | ExprToken::Star | ExprToken::Slash) {
+    return Err(ResolveError::InvalidTransform(format!("expression cannot start with a binary operator: '{}'", input)));
+  }
+
+  let mut parser = ExprParser { tokens: &tokens, pos: 0 };
+  let node = parser.parse_expr()?;
+  if parser.pos != tokens.len() {
+    return Err(ResolveError::InvalidTransform(format!("unexpected trailing tokens in expression: '{}'", input)));
+  }
+
+  Ok(flatten_expr_node(&node))
+}
+
 pub fn example() -> Result<(), ResolveError> {
   let mut tokens: TokenSet = IndexMap::new();
 
@@ -1,14 +1,19 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use clap::{Args};
+use clap::Args;
 use platform_dirs::{AppDirs, UserDirs};
 use lazy_static::lazy_static;
+use tera::Tera;
 
+mod app;
 mod cli;
 mod config;
+mod css;
+mod design_token;
 mod transformer;
 mod utils;
 
+use app::StateBehavior;
 use cli::Cli;
 
 lazy_static! {
@@ -16,6 +21,19 @@ lazy_static! {
     .expect("Failed to get application directories");
   pub static ref USER_DIRS: UserDirs = UserDirs::new()
     .expect("Failed to get user directories");
+  /// The last successfully parsed+validated config, keyed by the content
+  /// hash of the project's `color-gen.toml` - lets `--watch` skip reparsing
+  /// when a debounced change didn't actually touch that file's contents.
+  static ref CONFIG_CACHE: std::sync::Mutex<Option<(u64, config::Config)>> = std::sync::Mutex::new(None);
+  pub static ref TEMPLATES: Tera = {
+    let mut tera = Tera::default();
+    tera.add_raw_templates(vec![
+      ("COLOR_TOKEN", "--{% if prefix %}{{ prefix }}-{% endif %}{{ palette_name }}-{{ tone }}"),
+      ("COLOR_BASE", "--{% if prefix %}{{ prefix }}-{% endif %}{{ palette_name }}"),
+      ("COLOR_KEY", "--{% if prefix %}{{ prefix }}-{% endif %}{{ palette_name }}-key"),
+    ]).expect("Failed to compile built-in CSS templates");
+    tera
+  };
 }
 
 #[derive(Args)]
@@ -72,7 +90,164 @@ fn main() {
   cli.register_commands::<config::cli::Commands, _>(config::cli::handle);
 
   let matches = cli.command.get_matches();
-  let is_watching = matches.get_flag("watch");
 
-  println!("Is watching: {}", is_watching);
+  let args = CliArgs {
+    cwd: matches.get_one::<PathBuf>("cwd").cloned(),
+    out_dir: matches.get_one::<PathBuf>("out_dir").cloned(),
+    watch: matches.get_flag("watch"),
+    quiet: matches.get_flag("quiet"),
+    verbose: matches.get_flag("verbose"),
+    no_color: matches.get_flag("no_color"),
+    dry_run: matches.get_flag("dry_run"),
+    no_cache: matches.get_flag("no_cache"),
+  };
+
+  if let Err(e) = run(&args) {
+    eprintln!("error: {}", e);
+    std::process::exit(1);
+  }
+}
+
+fn run(args: &CliArgs) -> Result<(), Box<dyn std::error::Error>> {
+  let cwd = match &args.cwd {
+    Some(cwd) => cwd.clone(),
+    None => std::env::current_dir()?,
+  };
+
+  if args.watch {
+    watch_and_generate(&cwd, args)
+  } else {
+    generate_once(&cwd, args).map(|_| ())
+  }
+}
+
+fn hash_file_contents(path: &Path) -> Option<u64> {
+  use std::hash::{Hash, Hasher};
+  let bytes = std::fs::read(path).ok()?;
+  let mut hasher = std::collections::hash_map::DefaultHasher::new();
+  bytes.hash(&mut hasher);
+  Some(hasher.finish())
+}
+
+/// Load+validate the project config, reusing the last successful parse when
+/// the primary `color-gen.toml`'s content hash is unchanged from the last
+/// call - `--no-cache` bypasses this and always reparses from disk.
+fn resolve_validated_config(cwd: &Path, args: &CliArgs) -> Result<config::Config, Box<dyn std::error::Error>> {
+  let config_path = cwd.join("color-gen.toml");
+  let hash = if args.no_cache { None } else { hash_file_contents(&config_path) };
+
+  if let Some(h) = hash {
+    if let Some((cached_hash, cached_config)) = &*CONFIG_CACHE.lock().unwrap() {
+      if *cached_hash == h {
+        if args.verbose {
+          println!("{} unchanged, reusing cached config", config_path.display());
+        }
+        return Ok(cached_config.clone());
+      }
+    }
+  }
+
+  let state = app::AppState::Uninitialized.load_config(cwd)?;
+  let config = match state.validate()? {
+    app::AppState::Validated(config) => config,
+    _ => unreachable!("validate() always returns Validated or an error"),
+  };
+
+  if let Some(h) = hash {
+    *CONFIG_CACHE.lock().unwrap() = Some((h, config.clone()));
+  }
+
+  Ok(config)
+}
+
+/// Load config (honoring `--no-cache`), validate it, and (unless
+/// `--dry-run`) generate each theme's CSS file plus run the configured
+/// `[[transforms]]` pipeline, honoring `--quiet`/`--verbose` along the way.
+fn generate_once(cwd: &Path, args: &CliArgs) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+  let config = resolve_validated_config(cwd, args)?;
+
+  if args.dry_run {
+    if !args.quiet {
+      println!("Dry run: config loaded and validated, skipping CSS generation.");
+    }
+    return Ok(Vec::new());
+  }
+
+  let out_dir = args.out_dir.clone().unwrap_or_else(|| cwd.to_path_buf());
+  let mut written = match app::AppState::Validated(config).generate_css(&out_dir)? {
+    app::AppState::Generated(_, css_files) => {
+      if !args.quiet {
+        for file in &css_files {
+          println!("Generated {}", file.display());
+        }
+      }
+      css_files
+    }
+    _ => Vec::new(),
+  };
+
+  match transformer::run_transform_pipeline(Some(&out_dir)) {
+    Ok(transformed) => {
+      if !args.quiet {
+        for path in &transformed {
+          println!("Wrote {}", path.display());
+        }
+      }
+      written.extend(transformed);
+    }
+    Err(e) => eprintln!("warning: tokens transform pipeline failed: {}", e),
+  }
+
+  Ok(written)
+}
+
+/// Generate once up front, then rebuild on every filesystem change under
+/// `cwd`, debouncing bursts of events (e.g. an editor's save, or a git
+/// checkout touching many files at once) into a single rebuild.
+fn watch_and_generate(cwd: &Path, args: &CliArgs) -> Result<(), Box<dyn std::error::Error>> {
+  use std::sync::mpsc::channel;
+  use std::time::Duration;
+
+  use notify::{RecursiveMode, Watcher};
+
+  const DEBOUNCE: Duration = Duration::from_millis(300);
+
+  if !args.quiet {
+    println!("Watching {} for changes (Ctrl+C to stop)...", cwd.display());
+  }
+
+  if let Err(e) = generate_once(cwd, args) {
+    eprintln!("error: {}", e);
+  }
+
+  let (tx, rx) = channel::<notify::Result<notify::Event>>();
+  let mut watcher = notify::recommended_watcher(move |res| {
+    let _ = tx.send(res);
+  })?;
+  watcher.watch(cwd, RecursiveMode::Recursive)?;
+
+  loop {
+    let first = match rx.recv() {
+      Ok(Ok(event)) => event,
+      Ok(Err(e)) => {
+        eprintln!("watch error: {}", e);
+        continue;
+      }
+      Err(_) => break, // watcher (and its sender) was dropped
+    };
+
+    if args.verbose {
+      println!("change detected ({:?}), regenerating...", first.kind);
+    }
+
+    // Drain anything else that arrives within the debounce window so a
+    // burst of events triggers one rebuild instead of one per file.
+    while rx.recv_timeout(DEBOUNCE).is_ok() {}
+
+    if let Err(e) = generate_once(cwd, args) {
+      eprintln!("error: {}", e);
+    }
+  }
+
+  Ok(())
 }
\ No newline at end of file
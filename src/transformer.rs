@@ -1,10 +1,20 @@
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use thiserror::Error;
+
 use crate::config;
+use crate::design_token::{self, ResolveError, Token, TokenSet, TokenValue};
 
 
 pub mod cli {
+  use std::path::PathBuf;
+
   use clap::{Parser, Subcommand};
   use simply_colored::*;
-  
+
+  use crate::design_token;
+
   #[derive(Debug, Parser)]
   pub enum Commands {
     Tokens {
@@ -16,6 +26,11 @@ pub mod cli {
   #[derive(Debug, Subcommand)]
   enum SubCommands {
     Transform,
+    /// Import one or more DTCG-format token JSON files into a flat TokenSet.
+    Import {
+      #[arg(required = true, value_name = "FILE")]
+      files: Vec<PathBuf>,
+    },
   }
 
   pub fn handle(cmd: &Commands) {
@@ -24,16 +39,144 @@ pub mod cli {
         match subcommands {
           SubCommands::Transform => {
             println!("{DIM_MAGENTA}Transforming tokens...{RESET}");
-            
+            match crate::transformer::run_transform_pipeline(None) {
+              Ok(written) if written.is_empty() => println!("{DIM_MAGENTA}No transforms configured.{RESET}"),
+              Ok(written) => {
+                for path in &written {
+                  println!("{DIM_MAGENTA}Wrote {}{RESET}", path.display());
+                }
+              }
+              Err(e) => eprintln!("{DIM_MAGENTA}Failed to transform tokens: {}{RESET}", e),
+            }
+          },
+          SubCommands::Import { files } => match import_dtcg_files(files) {
+            Ok((tokens, validation_errors)) => {
+              println!(
+                "{DIM_MAGENTA}Imported {} token(s) from {} file(s).{RESET}",
+                tokens.len(),
+                files.len()
+              );
+              for err in &validation_errors {
+                eprintln!("{DIM_MAGENTA}warning: {}{RESET}", err);
+              }
+            }
+            Err(e) => eprintln!("{DIM_MAGENTA}Failed to import tokens: {}{RESET}", e),
           },
         }
       }
     }
   }
+
+  fn import_dtcg_files(
+    files: &[PathBuf],
+  ) -> Result<(design_token::TokenSet, Vec<design_token::TokenValidationError>), design_token::ResolveError> {
+    let mut sets = Vec::with_capacity(files.len());
+    let mut validation_errors = Vec::new();
+    for path in files {
+      let src = std::fs::read_to_string(path).map_err(|e| {
+        design_token::ResolveError::TransformFailed(format!("failed to read '{}': {}", path.display(), e))
+      })?;
+      let (tokens, errors) = design_token::load_tokens_from_dtcg_json_validated(&src)?;
+      validation_errors.extend(errors);
+      sets.push(tokens);
+    }
+    let tokens = design_token::merge_dtcg_token_sets(sets)?;
+    Ok((tokens, validation_errors))
+  }
+}
+
+#[derive(Debug, Error)]
+pub enum TransformPipelineError {
+  #[error("failed to load config: {0}")]
+  Config(#[from] config::ConfigError),
+  #[error("failed to resolve tokens: {0}")]
+  Resolve(#[from] ResolveError),
+  #[error("{0}")]
+  InvalidReferences(String),
+  #[error("failed to read/write '{path}': {source}")]
+  Io { path: PathBuf, source: std::io::Error },
+  #[error("failed to serialize tokens: {0}")]
+  Serialize(#[from] serde_json::Error),
+  #[error("target format {0:?} is not yet supported by `tokens transform`")]
+  UnsupportedFormat(config::TargetFormat),
+}
+
+fn resolve_config() -> Result<config::Config, config::ConfigError> {
+  let cwd = std::env::current_dir().map_err(|e| config::ConfigError::Io { path: PathBuf::from("."), source: e })?;
+  config::layered_config(&cwd)
+}
+
+fn load_source_tokens(path: &Path) -> Result<TokenSet, TransformPipelineError> {
+  let src = std::fs::read_to_string(path).map_err(|e| TransformPipelineError::Io { path: path.to_path_buf(), source: e })?;
+  Ok(design_token::load_tokens_from_dtcg_json(&src)?)
+}
+
+fn write_output(path: &Path, contents: &str) -> Result<(), TransformPipelineError> {
+  if let Some(parent) = path.parent() {
+    if !parent.as_os_str().is_empty() {
+      std::fs::create_dir_all(parent).map_err(|e| TransformPipelineError::Io { path: parent.to_path_buf(), source: e })?;
+    }
+  }
+  std::fs::write(path, contents).map_err(|e| TransformPipelineError::Io { path: path.to_path_buf(), source: e })
+}
+
+/// Warn (without failing) when two distinct token paths normalize to the
+/// same CSS custom-property name - `find_duplicates` was written for
+/// detecting exactly this kind of collision.
+fn warn_on_css_key_collisions(tokens: &TokenSet) {
+  let keys: Vec<String> = tokens.keys().map(|path| design_token::make_css_custom_property_key(path, &design_token::CssKeyOptions::default())).collect();
+  let duplicates = find_duplicates(keys);
+  if !duplicates.is_empty() {
+    eprintln!("warning: multiple tokens normalize to the same CSS custom property: {}", duplicates.join(", "));
+  }
+}
+
+/// A relative `target.output` is resolved against `out_dir` (typically the
+/// CLI's `--out`); an absolute one is used as-is regardless of `out_dir`.
+fn resolve_output_path(out_dir: Option<&Path>, output: &Path) -> PathBuf {
+  match out_dir {
+    Some(base) if output.is_relative() => base.join(output),
+    _ => output.to_path_buf(),
+  }
 }
 
-fn resolve_config() -> config::Config {
-  todo!();
+/// Run every configured `[[transforms]]` entry: load its source tokens,
+/// validate them (reporting every cycle/missing reference at once rather
+/// than stopping at the first one), resolve aliases/references, then write
+/// each of its targets in the requested format, with each target's own
+/// `css_prefix`/`css_separator`/`css_lowercase` controlling its `Css`
+/// output's key style and relative output paths resolved against `out_dir`.
+pub fn run_transform_pipeline(out_dir: Option<&Path>) -> Result<Vec<PathBuf>, TransformPipelineError> {
+  let config = resolve_config()?;
+  let mut written = Vec::new();
+
+  for transform in &config.transforms {
+    let tokens = load_source_tokens(&transform.from)?;
+    validate_references(&tokens)?;
+    let resolved = design_token::resolve_tokens(&tokens)?;
+    warn_on_css_key_collisions(&resolved);
+
+    for target in &transform.to {
+      let css_opts = design_token::CssKeyOptions {
+        prefix: target.css_prefix.clone(),
+        separator: target.css_separator.unwrap_or('-'),
+        lowercase: target.css_lowercase.unwrap_or(true),
+      };
+
+      let contents = match target.format {
+        config::TargetFormat::Css => design_token::emit_css(&resolved, ":root", &css_opts),
+        config::TargetFormat::Json => serde_json::to_string_pretty(&design_token::tokens_to_nested_dtcg_json(&resolved))?,
+        config::TargetFormat::JsonFlat => serde_json::to_string_pretty(&design_token::to_resolved_string_map(&resolved))?,
+        ref other => return Err(TransformPipelineError::UnsupportedFormat(other.clone())),
+      };
+
+      let output_path = resolve_output_path(out_dir, &target.output);
+      write_output(&output_path, &contents)?;
+      written.push(output_path);
+    }
+  }
+
+  Ok(written)
 }
 
 fn find_duplicates(tokens: Vec<String>) -> Vec<String> {
@@ -49,6 +192,239 @@ fn find_duplicates(tokens: Vec<String>) -> Vec<String> {
     duplicates.into_iter().collect()
 }
 
-fn resolve_references() {
-  todo!()
-}
\ No newline at end of file
+/// Find every `{dotted.path}` reference embedded in a string value, e.g. the
+/// `spacing.base` in `"calc({spacing.base} * 2)"` - not just dedicated
+/// `Alias`/`Reference` token values.
+fn extract_inline_refs(s: &str) -> Vec<String> {
+  let mut refs = Vec::new();
+  let chars: Vec<char> = s.chars().collect();
+  let mut i = 0;
+
+  while i < chars.len() {
+    if chars[i] == '{' {
+      let start = i + 1;
+      let mut j = start;
+      while j < chars.len() && chars[j] != '}' {
+        j += 1;
+      }
+      if j < chars.len() {
+        let path: String = chars[start..j].iter().collect();
+        refs.push(path.trim().to_string());
+        i = j + 1;
+        continue;
+      }
+    }
+    i += 1;
+  }
+
+  refs
+}
+
+fn substitute_inline_refs(s: &str, resolved: &IndexMap<String, String>) -> String {
+  let mut out = String::new();
+  let chars: Vec<char> = s.chars().collect();
+  let mut i = 0;
+
+  while i < chars.len() {
+    if chars[i] == '{' {
+      let start = i + 1;
+      let mut j = start;
+      while j < chars.len() && chars[j] != '}' {
+        j += 1;
+      }
+      if j < chars.len() {
+        let path: String = chars[start..j].iter().collect::<String>().trim().to_string();
+        match resolved.get(&path) {
+          Some(v) => out.push_str(v),
+          None => {
+            out.push('{');
+            out.push_str(&path);
+            out.push('}');
+          }
+        }
+        i = j + 1;
+        continue;
+      }
+    }
+    out.push(chars[i]);
+    i += 1;
+  }
+
+  out
+}
+
+/// The token paths a value immediately depends on, for graph/cycle purposes -
+/// `Alias`/`Reference` targets plus any inline `{path}` refs inside strings.
+fn direct_dependencies(value: &TokenValue) -> Vec<String> {
+  match value {
+    TokenValue::Alias(path) | TokenValue::Reference(path) => vec![path.clone()],
+    TokenValue::String(s) => extract_inline_refs(s),
+    TokenValue::Object(map) => map.values().flat_map(direct_dependencies).collect(),
+    _ => Vec::new(),
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum VisitMark {
+  InProgress,
+  Done,
+}
+
+/// DFS over the dependency graph, collecting every cycle and missing
+/// reference it finds rather than stopping at the first one - callers see
+/// all the problems with a token set in a single `resolve_references` call.
+fn visit_token(
+  key: &str,
+  tokens: &TokenSet,
+  marks: &mut std::collections::HashMap<String, VisitMark>,
+  order: &mut Vec<String>,
+  path: &mut Vec<String>,
+  errors: &mut Vec<ResolveError>,
+) {
+  match marks.get(key) {
+    Some(VisitMark::Done) => return,
+    Some(VisitMark::InProgress) => {
+      let cycle_start = path.iter().position(|k| k == key).unwrap_or(0);
+      let mut cycle: Vec<String> = path[cycle_start..].to_vec();
+      cycle.push(key.to_string());
+      errors.push(ResolveError::CycleDetected(cycle.join(" -> ")));
+      return;
+    }
+    None => {}
+  }
+
+  let Some(token) = tokens.get(key) else {
+    return;
+  };
+
+  marks.insert(key.to_string(), VisitMark::InProgress);
+  path.push(key.to_string());
+
+  for dep in direct_dependencies(&token.value) {
+    if !tokens.contains_key(&dep) {
+      errors.push(ResolveError::TokenNotFound(format!("{} (referenced by {})", dep, key)));
+      continue;
+    }
+    visit_token(&dep, tokens, marks, order, path, errors);
+  }
+
+  path.pop();
+  marks.insert(key.to_string(), VisitMark::Done);
+  order.push(key.to_string());
+}
+
+/// `resolved_values` preserves the actual resolved type of an aliased token
+/// (a `Number`/`Dimension`/`Bool` alias target stays that type); `resolved_strings`
+/// is the stringified form needed to splice a dependency into a `{path}`
+/// inline reference inside another string.
+fn resolve_token_value(
+  value: &TokenValue,
+  resolved_values: &IndexMap<String, TokenValue>,
+  resolved_strings: &IndexMap<String, String>,
+) -> TokenValue {
+  match value {
+    TokenValue::Alias(path) => resolved_values.get(path).cloned().unwrap_or_else(|| value.clone()),
+    TokenValue::Reference(path) => TokenValue::String(format!("var(--{})", path.replace('.', "-"))),
+    TokenValue::String(s) => TokenValue::String(substitute_inline_refs(s, resolved_strings)),
+    TokenValue::Object(map) => {
+      let mut out = IndexMap::new();
+      for (k, v) in map.iter() {
+        out.insert(k.clone(), resolve_token_value(v, resolved_values, resolved_strings));
+      }
+      TokenValue::Object(out)
+    }
+    other => other.clone(),
+  }
+}
+
+/// Recursively resolve aliases, CSS-var references, and inline `{path}`
+/// refs embedded in strings, following transitive chains in a single pass.
+/// Unlike `design_token::resolve_tokens`, this does not stop at the first
+/// problem: every cycle and every missing-path reference in the set is
+/// collected and returned together.
+pub fn resolve_references(tokens: &TokenSet) -> Result<TokenSet, Vec<ResolveError>> {
+  let mut marks = std::collections::HashMap::new();
+  let mut order = Vec::new();
+  let mut errors = Vec::new();
+
+  for key in tokens.keys() {
+    let mut path = Vec::new();
+    visit_token(key, tokens, &mut marks, &mut order, &mut path, &mut errors);
+  }
+
+  if !errors.is_empty() {
+    return Err(errors);
+  }
+
+  let mut resolved_values: IndexMap<String, TokenValue> = IndexMap::new();
+  let mut resolved_strings: IndexMap<String, String> = IndexMap::new();
+  let mut resolved: TokenSet = IndexMap::new();
+
+  for key in &order {
+    let token = tokens.get(key).expect("topological order only contains keys present in `tokens`");
+    let value = resolve_token_value(&token.value, &resolved_values, &resolved_strings);
+    resolved_strings.insert(key.clone(), design_token::token_value_to_string(&value));
+    resolved_values.insert(key.clone(), value.clone());
+    resolved.insert(key.clone(), Token { name: key.clone(), value, comment: token.comment.clone() });
+  }
+
+  Ok(resolved)
+}
+
+/// Pre-flight validation pass ahead of `design_token::resolve_tokens`: unlike
+/// that resolver (which stops at the first cycle or missing reference),
+/// `resolve_references` walks the whole dependency graph and reports every
+/// problem it finds, so a `tokens transform` run surfaces all of them at once
+/// instead of making the author fix and re-run one at a time.
+fn validate_references(tokens: &TokenSet) -> Result<(), TransformPipelineError> {
+  resolve_references(tokens).map(|_| ()).map_err(|errors| {
+    let message = format!(
+      "{} reference error(s) found: {}",
+      errors.len(),
+      errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ")
+    );
+    TransformPipelineError::InvalidReferences(message)
+  })
+}
+
+#[cfg(test)]
+mod resolve_references_tests {
+  use super::*;
+
+  fn token(value: TokenValue) -> Token {
+    Token { name: String::new(), value, comment: None }
+  }
+
+  #[test]
+  fn reports_multiple_simultaneous_cycles_and_missing_refs_together() {
+    let mut tokens: TokenSet = IndexMap::new();
+    // a -> b -> a (cycle)
+    tokens.insert("a".to_string(), token(TokenValue::Alias("b".to_string())));
+    tokens.insert("b".to_string(), token(TokenValue::Alias("a".to_string())));
+    // c -> d -> c (a second, independent cycle)
+    tokens.insert("c".to_string(), token(TokenValue::Alias("d".to_string())));
+    tokens.insert("d".to_string(), token(TokenValue::Alias("c".to_string())));
+    // e -> missing (a missing reference, unrelated to either cycle)
+    tokens.insert("e".to_string(), token(TokenValue::Alias("missing".to_string())));
+
+    let errors = resolve_references(&tokens).expect_err("expects cycles and a missing ref");
+
+    let cycles = errors.iter().filter(|e| matches!(e, ResolveError::CycleDetected(_))).count();
+    let missing = errors.iter().filter(|e| matches!(e, ResolveError::TokenNotFound(_))).count();
+    assert_eq!(cycles, 2, "expected both independent cycles to be reported: {:?}", errors);
+    assert_eq!(missing, 1, "expected the missing reference to be reported: {:?}", errors);
+  }
+
+  #[test]
+  fn alias_preserves_the_target_s_resolved_type() {
+    let mut tokens: TokenSet = IndexMap::new();
+    tokens.insert("spacing.base".to_string(), token(TokenValue::Dimension { value: 4.0, unit: "px".to_string() }));
+    tokens.insert("spacing.alias".to_string(), token(TokenValue::Alias("spacing.base".to_string())));
+
+    let resolved = resolve_references(&tokens).expect("no cycles or missing refs");
+    assert_eq!(
+      resolved.get("spacing.alias").unwrap().value,
+      TokenValue::Dimension { value: 4.0, unit: "px".to_string() }
+    );
+  }
+}